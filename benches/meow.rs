@@ -0,0 +1,95 @@
+use ck_meow::Meow;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: [usize; 3] = [64, 4 * 1024, 1024 * 1024];
+
+fn bench_ad(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ad");
+    for size in SIZES {
+        let data = vec![0xAA; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            let mut meow = Meow::new(b"bench");
+            b.iter(|| meow.ad(data, false));
+        });
+    }
+    group.finish();
+}
+
+fn bench_send_enc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("send_enc");
+    for size in SIZES {
+        let mut data = vec![0xAA; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            let mut meow = Meow::new(b"bench");
+            meow.key(&[0xFF; 32], false);
+            b.iter(|| meow.send_enc(&mut data, false));
+        });
+    }
+    group.finish();
+}
+
+fn bench_recv_enc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recv_enc");
+    for size in SIZES {
+        let mut data = vec![0xAA; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            let mut meow = Meow::new(b"bench");
+            meow.key(&[0xFF; 32], false);
+            b.iter(|| meow.recv_enc(&mut data, false));
+        });
+    }
+    group.finish();
+}
+
+fn bench_prf(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prf");
+    for size in SIZES {
+        let mut data = vec![0u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            let mut meow = Meow::new(b"bench");
+            b.iter(|| meow.prf(&mut data, false));
+        });
+    }
+    group.finish();
+}
+
+fn bench_hash_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_many");
+    for size in SIZES {
+        let data = vec![0xAA; size];
+        let mut outputs = [[0u8; 32]; 4];
+        group.throughput(Throughput::Bytes(4 * size as u64));
+        group.bench_function(BenchmarkId::new("separate", size), |b| {
+            let meow = Meow::new(b"bench");
+            b.iter(|| {
+                for output in &mut outputs {
+                    let mut meow = meow.clone();
+                    meow.ad(&data, false);
+                    meow.prf(output, false);
+                }
+            });
+        });
+        group.bench_function(BenchmarkId::new("batched", size), |b| {
+            let meow = Meow::new(b"bench");
+            b.iter(|| {
+                let [o0, o1, o2, o3] = &mut outputs;
+                meow.hash_many([&data; 4], [o0, o1, o2, o3]);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_ad,
+    bench_send_enc,
+    bench_recv_enc,
+    bench_prf,
+    bench_hash_many
+);
+criterion_main!(benches);
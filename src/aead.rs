@@ -0,0 +1,198 @@
+use ::aead::{
+    consts::{U0, U16, U32},
+    AeadCore, AeadInPlace, Error, Key, KeyInit, KeySizeUser, Nonce, Tag,
+};
+
+use crate::meow::Meow;
+
+/// The protocol string used by [`MeowAead`].
+const AEAD_PROTOCOL: &[u8] = b"ck-meow AEAD";
+
+/// An AEAD built on top of Meow.
+///
+/// This implements the traits from the `aead` crate, using 32 byte keys,
+/// 16 byte nonces, and 16 byte tags. Nonces must never be reused with the same key.
+///
+/// Encrypting a message does the following operations:
+///
+/// ```
+/// use ck_meow::Meow;
+///
+/// # let (key, nonce, associated_data) = ([0u8; 32], [0u8; 16], b"");
+/// # let mut message = *b"hello";
+/// let mut meow = Meow::new(b"ck-meow AEAD");
+/// meow.key(&key, false);
+/// meow.send_clr(&nonce, false);
+/// meow.ad(associated_data, false);
+/// meow.send_enc(&mut message, false);
+/// let mut tag = [0u8; 16];
+/// meow.send_mac(&mut tag);
+/// ```
+///
+/// and decrypting a message does the same, with `recv_clr`, `recv_enc`,
+/// and `recv_mac` instead. The ciphertext is the encrypted message,
+/// and the tag gets appended to it, when not using the detached methods.
+///
+/// ```
+/// use ck_meow::MeowAead;
+/// use aead::{AeadInPlace, KeyInit};
+///
+/// let aead = MeowAead::new(&[0xAA; 32].into());
+/// let nonce = [0xBB; 16].into();
+/// let mut buffer = *b"hello world!";
+/// let tag = aead
+///     .encrypt_in_place_detached(&nonce, b"associated data", &mut buffer)
+///     .unwrap();
+/// aead.decrypt_in_place_detached(&nonce, b"associated data", &mut buffer, &tag)
+///     .unwrap();
+/// assert_eq!(&buffer, b"hello world!");
+/// ```
+#[derive(Clone)]
+pub struct MeowAead {
+    /// The state, after absorbing the key.
+    meow: Meow,
+}
+
+impl MeowAead {
+    /// Create the state used for a single message, up to the point of handling the data.
+    fn start(&self, nonce: &Nonce<Self>, associated_data: &[u8], send: bool) -> Meow {
+        let mut meow = self.meow.clone();
+        if send {
+            meow.send_clr(nonce, false);
+        } else {
+            meow.recv_clr(nonce, false);
+        }
+        meow.ad(associated_data, false);
+        meow
+    }
+}
+
+impl KeySizeUser for MeowAead {
+    type KeySize = U32;
+}
+
+impl KeyInit for MeowAead {
+    fn new(key: &Key<Self>) -> Self {
+        let mut meow = Meow::new(AEAD_PROTOCOL);
+        meow.key(key, false);
+        Self { meow }
+    }
+}
+
+impl AeadCore for MeowAead {
+    type NonceSize = U16;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for MeowAead {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<Tag<Self>, Error> {
+        let mut meow = self.start(nonce, associated_data, true);
+        meow.send_enc(buffer, false);
+        let mut tag = Tag::<Self>::default();
+        meow.send_mac(&mut tag);
+        Ok(tag)
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> Result<(), Error> {
+        let mut meow = self.start(nonce, associated_data, false);
+        let mut tag = *tag;
+        meow.recv_enc_and_mac(buffer, &mut tag).map_err(|_| Error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::seal_by_hand;
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let key = [1u8; 32];
+        let nonce = [2u8; 16];
+        let mut buffer = *b"some message";
+
+        let aead = MeowAead::new(&key.into());
+        let tag = aead
+            .encrypt_in_place_detached(&nonce.into(), b"ad", &mut buffer)
+            .unwrap();
+
+        let mut meow = Meow::new(AEAD_PROTOCOL);
+        meow.key(&key, false);
+        meow.send_clr(&nonce, false);
+        meow.ad(b"ad", false);
+        let (expected, expected_tag) = seal_by_hand(&mut meow, b"some message");
+
+        assert_eq!(buffer[..], expected[..]);
+        assert_eq!(tag[..], expected_tag);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let aead = MeowAead::new(&[1u8; 32].into());
+        let nonce = [2u8; 16].into();
+        let mut buffer = *b"some message";
+        let tag = aead
+            .encrypt_in_place_detached(&nonce, b"ad", &mut buffer)
+            .unwrap();
+
+        let mut ok = buffer;
+        assert!(aead
+            .decrypt_in_place_detached(&nonce, b"ad", &mut ok, &tag)
+            .is_ok());
+        assert_eq!(&ok, b"some message");
+
+        let mut bad_buffer = buffer;
+        bad_buffer[0] ^= 1;
+        let mut bad_tag = tag;
+        bad_tag[15] ^= 1;
+        let other_nonce = [3u8; 16].into();
+        let cases = [
+            (&nonce, &b"ad"[..], buffer, tag),
+            (&nonce, &b"AD"[..], buffer, tag),
+            (&other_nonce, &b"ad"[..], buffer, tag),
+            (&nonce, &b"ad"[..], bad_buffer, tag),
+            (&nonce, &b"ad"[..], buffer, bad_tag),
+        ];
+        for (i, (nonce, ad, mut buffer, tag)) in cases.into_iter().enumerate() {
+            let result = aead.decrypt_in_place_detached(nonce, ad, &mut buffer, &tag);
+            if i == 0 {
+                assert!(result.is_ok());
+            } else {
+                assert!(result.is_err());
+                assert_eq!(buffer, [0u8; 12]);
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_aead_roundtrip() {
+        use ::aead::{Aead, Payload};
+
+        let aead = MeowAead::new(&[1u8; 32].into());
+        let nonce = [2u8; 16].into();
+        let payload = Payload {
+            msg: b"some message",
+            aad: b"ad",
+        };
+        let ciphertext = aead.encrypt(&nonce, payload).unwrap();
+        assert_eq!(ciphertext.len(), 12 + 16);
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: b"ad",
+        };
+        assert_eq!(aead.decrypt(&nonce, payload).unwrap(), b"some message");
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    channel::{Receiver, Sender},
+    session::{Session, SessionError, RECORD_HEADER_LEN, RECORD_OVERHEAD},
+};
+
+/// Seal a message into a record, appending it to a buffer.
+fn encode(session: &mut Session, message: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+    let start = dst.len();
+    dst.resize(start + RECORD_OVERHEAD + message.len(), 0);
+    if let Err(e) = session.seal_into(message, &mut dst[start..]) {
+        dst.truncate(start);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Open the first record in a buffer, if it's complete.
+fn decode(session: &mut Session, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+    let Some(header) = src.get(..RECORD_HEADER_LEN) else {
+        return Ok(None);
+    };
+    let header: [u8; RECORD_HEADER_LEN] = header.try_into().expect("header has the right length");
+    // Reject large records before waiting for them to arrive.
+    if u32::from_le_bytes(header) as usize > session.max_record_size() {
+        return Err(SessionError::TooLarge.into());
+    }
+    let len = Session::record_len(header).ok_or(SessionError::TooLarge)?;
+    if src.len() < len {
+        src.reserve(len - src.len());
+        return Ok(None);
+    }
+    let record = src.split_to(len);
+    let mut message = BytesMut::zeroed(len - RECORD_OVERHEAD);
+    session.open_into(&record, &mut message)?;
+    Ok(Some(message))
+}
+
+/// Encode messages as length-delimited records.
+///
+/// Since a session has a single state, the records need to be decoded in the
+/// same order that they were encoded, across both directions.
+impl Encoder<&[u8]> for Session {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        encode(self, item, dst)
+    }
+}
+
+/// Decode length-delimited records into messages.
+impl Decoder for Session {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        decode(self, src)
+    }
+}
+
+/// Encode messages as length-delimited records, for one direction of a split state.
+///
+/// ```
+/// use ck_meow::{Meow, Role};
+/// use futures::{SinkExt, StreamExt};
+/// use tokio_util::codec::{FramedRead, FramedWrite};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut meow = Meow::new(b"my protocol");
+/// meow.key(b"a shared secret", false);
+/// let (sender, _) = meow.clone().split(Role::Initiator);
+/// let (_, receiver) = meow.split(Role::Responder);
+///
+/// let (client, server) = tokio::io::duplex(1024);
+/// let mut frames_out = FramedWrite::new(client, sender);
+/// let mut frames_in = FramedRead::new(server, receiver);
+/// frames_out.send(&b"hello"[..]).await.unwrap();
+/// assert_eq!(frames_in.next().await.unwrap().unwrap(), &b"hello"[..]);
+/// # });
+/// ```
+impl Encoder<&[u8]> for Sender {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        encode(&mut self.session, item, dst)
+    }
+}
+
+/// Decode length-delimited records into messages, for one direction of a split state.
+impl Decoder for Receiver {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        decode(&mut self.session, src)
+    }
+}
+
+/// An async writer, sealing data into records.
+///
+/// Each write seals as much data as fits in a single record, and each record
+/// has its own MAC. Shutting down the writer sends an empty record, marking
+/// the end of the data, so that [`AsyncDecryptReader`] can detect truncation.
+///
+/// ```
+/// use ck_meow::{AsyncDecryptReader, AsyncEncryptWriter, Meow, Role};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut meow = Meow::new(b"my protocol");
+/// meow.key(b"a shared secret", false);
+/// let (sender, _) = meow.clone().split(Role::Initiator);
+/// let (_, receiver) = meow.split(Role::Responder);
+///
+/// let (client, server) = tokio::io::duplex(1024);
+/// let mut writer = AsyncEncryptWriter::new(sender, client);
+/// let mut reader = AsyncDecryptReader::new(receiver, server);
+/// writer.write_all(b"hello").await.unwrap();
+/// writer.shutdown().await.unwrap();
+/// let mut data = Vec::new();
+/// reader.read_to_end(&mut data).await.unwrap();
+/// assert_eq!(data, b"hello");
+/// # });
+/// ```
+pub struct AsyncEncryptWriter<W> {
+    sender: Sender,
+    inner: W,
+    /// A sealed record, which hasn't been completely written yet.
+    pending: BytesMut,
+    closed: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncryptWriter<W> {
+    /// Create a new writer, sealing records with one half of a split state.
+    pub fn new(sender: Sender, inner: W) -> Self {
+        Self {
+            sender,
+            inner,
+            pending: BytesMut::new(),
+            closed: false,
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Write out the pending record, if there is one.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            let _ = self.pending.split_to(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        ready!(self.poll_pending(cx))?;
+        // Empty records are reserved for marking the end of the data.
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let this = &mut *self;
+        let len = buf.len().min(this.sender.max_record_size());
+        encode(&mut this.sender.session, &buf[..len], &mut this.pending)?;
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_pending(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_pending(cx))?;
+        if !self.closed {
+            let this = &mut *self;
+            encode(&mut this.sender.session, &[], &mut this.pending)?;
+            this.closed = true;
+            ready!(this.poll_pending(cx))?;
+        }
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// An async reader, opening records written by an [`AsyncEncryptWriter`].
+///
+/// Data is only returned once the record containing it has been authenticated.
+/// If a record fails to authenticate, or the data ends without the empty record
+/// marking its end, reading returns an error.
+pub struct AsyncDecryptReader<R> {
+    receiver: Receiver,
+    inner: R,
+    /// Data read from the underlying reader, which hasn't been opened yet.
+    buffer: BytesMut,
+    /// The message from the last record, which hasn't been returned yet.
+    message: BytesMut,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecryptReader<R> {
+    /// Create a new reader, opening records with one half of a split state.
+    pub fn new(receiver: Receiver, inner: R) -> Self {
+        Self {
+            receiver,
+            inner,
+            buffer: BytesMut::new(),
+            message: BytesMut::new(),
+            done: false,
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if !this.message.is_empty() {
+                let take = buf.remaining().min(this.message.len());
+                buf.put_slice(&this.message.split_to(take));
+                return Poll::Ready(Ok(()));
+            }
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+            if let Some(message) = decode(&mut this.receiver.session, &mut this.buffer)? {
+                this.done = message.is_empty();
+                this.message = message;
+                continue;
+            }
+            let n = ready!(tokio_util::io::poll_read_buf(
+                Pin::new(&mut this.inner),
+                cx,
+                &mut this.buffer
+            ))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use super::*;
+    use crate::{meow::Role, test::keyed};
+
+    fn halves() -> (Sender, Receiver) {
+        let (sender, _) = keyed().split(Role::Initiator);
+        let (_, receiver) = keyed().split(Role::Responder);
+        (sender, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_codec_roundtrip() {
+        let (sender, receiver) = halves();
+        let (client, server) = duplex(64);
+        let mut frames_out = FramedWrite::new(client, sender);
+        let mut frames_in = FramedRead::new(server, receiver);
+        let writer = async {
+            for i in 0..50u8 {
+                frames_out.send(&[i; 100][..]).await.unwrap();
+            }
+            frames_out.close().await.unwrap();
+        };
+        let reader = async {
+            for i in 0..50u8 {
+                let frame = frames_in.next().await.unwrap().unwrap();
+                assert_eq!(frame, &[i; 100][..]);
+            }
+            assert!(frames_in.next().await.is_none());
+        };
+        tokio::join!(writer, reader);
+    }
+
+    #[tokio::test]
+    async fn test_codec_rejects_tampering() {
+        let (mut sender, mut receiver) = halves();
+        let mut buffer = BytesMut::new();
+        sender.encode(b"hello", &mut buffer).unwrap();
+        sender.encode(b"world", &mut buffer).unwrap();
+        let mut partial = buffer.split_to(RECORD_OVERHEAD + 2);
+        assert!(receiver.decode(&mut partial).unwrap().is_none());
+        partial.extend_from_slice(&buffer.split_to(3));
+        assert_eq!(
+            receiver.decode(&mut partial).unwrap().unwrap(),
+            &b"hello"[..]
+        );
+        buffer[RECORD_HEADER_LEN] ^= 1;
+        assert!(receiver.decode(&mut buffer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_codec_rejects_large_headers_early() {
+        let (_, mut receiver) = halves();
+        let mut buffer = BytesMut::from(&u32::MAX.to_le_bytes()[..]);
+        assert!(receiver.decode(&mut buffer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reader_and_writer() {
+        let (sender, receiver) = halves();
+        let (sender, receiver) = (
+            sender.with_max_record_size(100),
+            receiver.with_max_record_size(100),
+        );
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let (client, server) = duplex(64);
+        let mut writer = AsyncEncryptWriter::new(sender, client);
+        let mut reader = AsyncDecryptReader::new(receiver, server);
+        let write = async {
+            for piece in data.chunks(77) {
+                writer.write_all(piece).await.unwrap();
+            }
+            writer.shutdown().await.unwrap();
+        };
+        let read = async {
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).await.unwrap();
+            out
+        };
+        let ((), out) = tokio::join!(write, read);
+        assert_eq!(out, data);
+    }
+
+    #[tokio::test]
+    async fn test_reader_detects_truncation() {
+        let (sender, receiver) = halves();
+        let (client, server) = duplex(1024);
+        let mut writer = AsyncEncryptWriter::new(sender, client);
+        let mut reader = AsyncDecryptReader::new(receiver, server);
+        writer.write_all(b"hello").await.unwrap();
+        writer.flush().await.unwrap();
+        // Dropping the writer without shutting it down doesn't mark the end of the data.
+        drop(writer);
+        let mut out = Vec::new();
+        let error = reader.read_to_end(&mut out).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(out, b"hello");
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    kitten::KittenState,
+    meow::{Meow, Security, SupportedSecurity, FLAG_A, FLAG_C, FLAG_I},
+    permutation::Permutation,
+};
+
+/// The operation a lane is currently working on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    /// Absorbing the message, after its header.
+    Ad,
+    /// Absorbing the header of the prf operation.
+    PrfHeader,
+    /// Squeezing the digest out of the state.
+    Prf,
+    /// The digest is complete.
+    Done,
+}
+
+/// A single state being driven alongside the others.
+struct Lane<'a, const SECURITY: usize, P: Permutation>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    meow: Meow<SECURITY, P>,
+    stage: Stage,
+    /// The header of the current operation, and how much of it remains.
+    header: [u8; 2],
+    header_left: usize,
+    message: &'a [u8],
+    output: &'a mut [u8],
+}
+
+impl<'a, const SECURITY: usize, P: Permutation> Lane<'a, SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    fn new(meow: Meow<SECURITY, P>, message: &'a [u8], output: &'a mut [u8]) -> Self {
+        let mut out = Self {
+            meow,
+            stage: Stage::Ad,
+            header: [0; 2],
+            header_left: 0,
+            message,
+            output,
+        };
+        out.begin(FLAG_A, Stage::Ad);
+        out
+    }
+
+    fn begin(&mut self, flags: u8, stage: Stage) {
+        self.header = self.meow.op_header(flags);
+        self.header_left = self.header.len();
+        self.stage = stage;
+    }
+
+    /// Absorb whatever remains of the current header.
+    fn absorb_header(&mut self) {
+        let start = self.header.len() - self.header_left;
+        let header = self.header;
+        self.header_left = self.meow.absorb_in_block(&header[start..]).len();
+    }
+
+    /// Make as much progress as possible, returning whether F needs to run.
+    ///
+    /// This mirrors what `ad` followed by `prf` would do, except that we stop
+    /// whenever the permutation needs to be applied.
+    fn advance(&mut self) -> bool {
+        loop {
+            if self.stage == Stage::Done {
+                return false;
+            }
+            if self.meow.block_full() {
+                return true;
+            }
+            match self.stage {
+                Stage::Ad if self.header_left > 0 => self.absorb_header(),
+                Stage::Ad if !self.message.is_empty() => {
+                    self.message = self.meow.absorb_in_block(self.message);
+                }
+                Stage::Ad => self.begin(FLAG_I | FLAG_A | FLAG_C, Stage::PrfHeader),
+                Stage::PrfHeader if self.header_left > 0 => self.absorb_header(),
+                Stage::PrfHeader => {
+                    self.stage = Stage::Prf;
+                    // This operation forces F to run, unless it just did.
+                    if !self.meow.block_empty() {
+                        return true;
+                    }
+                }
+                Stage::Prf if !self.output.is_empty() => {
+                    let output = core::mem::take(&mut self.output);
+                    self.output = self.meow.squeeze_in_block(output);
+                    // The state gets thrown away, so there's no point in running F again.
+                    if self.output.is_empty() {
+                        self.stage = Stage::Done;
+                    }
+                }
+                Stage::Prf | Stage::Done => self.stage = Stage::Done,
+            }
+        }
+    }
+}
+
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// Hash several messages at once, starting from this state.
+    ///
+    /// For each message, this produces the same output as cloning this state,
+    /// and then calling `ad` with the message, followed by `prf` with the output.
+    ///
+    /// The difference is that all of the states are processed in lockstep,
+    /// which allows applying the permutation to all of them at the same time.
+    /// On targets with vector rotation instructions, like AVX-512, this is a
+    /// lot faster than hashing each message on its own, especially when the
+    /// messages have similar lengths. Batches of 2, 4, or 8 messages work best.
+    ///
+    /// ```
+    /// use ck_meow::Meow;
+    ///
+    /// let mut outputs = [[0u8; 32]; 2];
+    /// let [out0, out1] = &mut outputs;
+    /// Meow::new(b"batch example").hash_many([b"cat", b"dog"], [out0, out1]);
+    ///
+    /// let mut meow = Meow::new(b"batch example");
+    /// meow.ad(b"dog", false);
+    /// let mut expected = [0u8; 32];
+    /// meow.prf(&mut expected, false);
+    /// assert_eq!(outputs[1], expected);
+    /// ```
+    pub fn hash_many<const N: usize>(&self, messages: [&[u8]; N], outputs: [&mut [u8]; N]) {
+        let mut outputs = outputs.into_iter();
+        let mut lanes = messages.map(|message| {
+            // The iterator has exactly N elements, so this will never fail.
+            let output = outputs.next().unwrap();
+            Lane::new(self.clone(), message, output)
+        });
+        loop {
+            let mut any = false;
+            let states: [Option<&mut KittenState<P>>; N] = lanes.each_mut().map(|lane| {
+                if !lane.advance() {
+                    return None;
+                }
+                any = true;
+                lane.meow.pad_f();
+                Some(lane.meow.state_mut())
+            });
+            if !any {
+                return;
+            }
+            KittenState::permute_many(states);
+            for lane in &mut lanes {
+                if lane.stage != Stage::Done {
+                    lane.meow.finish_f();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{KeccakF1600, KitTen, Meow, Permutation, Security, SupportedSecurity};
+
+    const LENGTHS: [usize; 16] = [
+        0, 1, 2, 100, 132, 133, 134, 135, 163, 164, 165, 166, 167, 168, 300, 1000,
+    ];
+
+    fn expected<const SECURITY: usize, P: Permutation>(
+        meow: &Meow<SECURITY, P>,
+        message: &[u8],
+        output: &mut [u8],
+    ) where
+        Security<SECURITY>: SupportedSecurity,
+    {
+        let mut meow = meow.clone();
+        meow.ad(message, false);
+        meow.prf(output, false);
+    }
+
+    fn check<const N: usize, const SECURITY: usize, P: Permutation>(meow: Meow<SECURITY, P>)
+    where
+        Security<SECURITY>: SupportedSecurity,
+    {
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        for (i, &first) in LENGTHS.iter().enumerate() {
+            let messages: [&[u8]; N] = core::array::from_fn(|l| {
+                &data[..LENGTHS[(i + 3 * l) % LENGTHS.len()].max(first * (l % 2))]
+            });
+            let output_lens: [usize; N] =
+                core::array::from_fn(|l| LENGTHS[(i + 5 * l + 1) % LENGTHS.len()]);
+            let mut outputs: [Vec<u8>; N] = output_lens.map(|len| vec![0; len]);
+            meow.hash_many(messages, outputs.each_mut().map(|o| o.as_mut_slice()));
+            for l in 0..N {
+                let mut expect = vec![0; output_lens[l]];
+                expected(&meow, messages[l], &mut expect);
+                assert_eq!(outputs[l], expect, "lane {} of {}", l, N);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_many_matches_separate_hashes() {
+        check::<2, 128, KitTen>(Meow::new(b"batch test"));
+        check::<4, 128, KitTen>(Meow::new(b"batch test"));
+        check::<8, 128, KitTen>(Meow::new(b"batch test"));
+        check::<4, 256, KeccakF1600>(Meow::init(b"batch test"));
+    }
+
+    #[test]
+    fn test_hash_many_after_other_operations() {
+        let mut meow = Meow::new(b"batch test");
+        meow.key(b"key", false);
+        meow.ad(b"partial", false);
+        check::<4, 128, KitTen>(meow);
+    }
+}
@@ -0,0 +1,292 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    meow::{Meow, Role},
+    session::{Session, SessionError},
+};
+
+/// The label for the state carrying records from the initiator to the responder.
+const INITIATOR_TO_RESPONDER: &[u8] = b"initiator to responder";
+/// The label for the state carrying records from the responder to the initiator.
+const RESPONDER_TO_INITIATOR: &[u8] = b"responder to initiator";
+
+/// Derive the state for one direction.
+fn direction(mut meow: Meow, label: &[u8]) -> Meow {
+    meow.meta_ad(label, false);
+    meow.ratchet();
+    // Whoever sends first in this direction is the initiator, regardless
+    // of the roles used before splitting.
+    meow.role = Role::Undecided;
+    meow
+}
+
+impl Meow {
+    /// Split a keyed state into two halves, for sending and receiving records at the same time.
+    ///
+    /// A single state requires both parties to agree on the order of all of their
+    /// operations. Splitting it derives two independent states, one for each direction,
+    /// which are domain separated from each other:
+    ///
+    /// ```
+    /// use ck_meow::{Meow, Role, Session};
+    ///
+    /// # let meow = Meow::new(b"my protocol");
+    /// # let (mut sender, _) = meow.clone().split(Role::Initiator);
+    /// let mut initiator_to_responder = meow.clone();
+    /// initiator_to_responder.meta_ad(b"initiator to responder", false);
+    /// initiator_to_responder.ratchet();
+    /// let mut responder_to_initiator = meow;
+    /// responder_to_initiator.meta_ad(b"responder to initiator", false);
+    /// responder_to_initiator.ratchet();
+    /// # let mut record = [0u8; 32];
+    /// # let len = sender.seal_into(b"hello", &mut record).unwrap();
+    /// # let mut receiver = Session::new(initiator_to_responder);
+    /// # assert_eq!(receiver.open_into(&record[..len], &mut [0u8; 32]), Ok(5));
+    /// ```
+    ///
+    /// Each state also forgets its role, so the roles used before splitting don't matter.
+    /// Each state is used by a [`Session`], with one party sealing records,
+    /// and the other opening them. The role says which party we are, and so which
+    /// state we send with; the other party needs to use the opposite role.
+    /// The halves can be moved to different threads.
+    ///
+    /// ```
+    /// use ck_meow::{Meow, Role};
+    ///
+    /// let mut meow = Meow::new(b"my protocol");
+    /// meow.key(b"a shared secret", false);
+    /// let (mut client_sender, mut client_receiver) = meow.clone().split(Role::Initiator);
+    /// let (mut server_sender, mut server_receiver) = meow.split(Role::Responder);
+    ///
+    /// let mut request = [0u8; 64];
+    /// let mut response = [0u8; 64];
+    /// let request_len = client_sender.seal_into(b"request", &mut request).unwrap();
+    /// let response_len = server_sender.seal_into(b"response", &mut response).unwrap();
+    ///
+    /// let mut message = [0u8; 64];
+    /// let len = client_receiver.open_into(&response[..response_len], &mut message).unwrap();
+    /// assert_eq!(&message[..len], b"response");
+    /// let len = server_receiver.open_into(&request[..request_len], &mut message).unwrap();
+    /// assert_eq!(&message[..len], b"request");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This panics if the role is [`Role::Undecided`].
+    pub fn split(self, role: Role) -> (Sender, Receiver) {
+        let (send_label, recv_label) = match role {
+            Role::Initiator => (INITIATOR_TO_RESPONDER, RESPONDER_TO_INITIATOR),
+            Role::Responder => (RESPONDER_TO_INITIATOR, INITIATOR_TO_RESPONDER),
+            Role::Undecided => panic!("splitting requires a decided role"),
+        };
+        let sender = Sender {
+            session: Session::new(direction(self.clone(), send_label)),
+        };
+        let receiver = Receiver {
+            session: Session::new(direction(self, recv_label)),
+        };
+        (sender, receiver)
+    }
+}
+
+/// The half of a split state which seals records.
+///
+/// This is created with [`Meow::split`], and works like [`Session::seal_into`].
+pub struct Sender {
+    pub(crate) session: Session,
+}
+
+impl Sender {
+    /// Set the maximum number of bytes in a message.
+    ///
+    /// See [`Session::with_max_record_size`].
+    pub fn with_max_record_size(self, max_record_size: usize) -> Self {
+        Self {
+            session: self.session.with_max_record_size(max_record_size),
+        }
+    }
+
+    /// Set how many records, or bytes of messages, to seal between ratchets.
+    ///
+    /// See [`Session::with_ratchet_interval`].
+    pub fn with_ratchet_interval(self, records: u64, bytes: u64) -> Self {
+        Self {
+            session: self.session.with_ratchet_interval(records, bytes),
+        }
+    }
+
+    /// The maximum number of bytes in a message.
+    pub fn max_record_size(&self) -> usize {
+        self.session.max_record_size()
+    }
+
+    /// The number of records sealed so far.
+    pub fn sequence(&self) -> u64 {
+        self.session.sequence()
+    }
+
+    /// Seal a message into a record, returning the length of the record.
+    pub fn seal_into(&mut self, message: &[u8], record: &mut [u8]) -> Result<usize, SessionError> {
+        self.session.seal_into(message, record)
+    }
+
+    /// Seal a message into a record.
+    #[cfg(feature = "alloc")]
+    pub fn seal(&mut self, message: &[u8]) -> Result<Vec<u8>, SessionError> {
+        self.session.seal(message)
+    }
+}
+
+/// The half of a split state which opens records.
+///
+/// This is created with [`Meow::split`], and works like [`Session::open_into`].
+/// If a record fails to authenticate, the receiver is poisoned, but the
+/// sender can still be used.
+pub struct Receiver {
+    pub(crate) session: Session,
+}
+
+impl Receiver {
+    /// Set the maximum number of bytes in a message.
+    ///
+    /// See [`Session::with_max_record_size`].
+    pub fn with_max_record_size(self, max_record_size: usize) -> Self {
+        Self {
+            session: self.session.with_max_record_size(max_record_size),
+        }
+    }
+
+    /// Set how many records, or bytes of messages, to open between ratchets.
+    ///
+    /// See [`Session::with_ratchet_interval`].
+    pub fn with_ratchet_interval(self, records: u64, bytes: u64) -> Self {
+        Self {
+            session: self.session.with_ratchet_interval(records, bytes),
+        }
+    }
+
+    /// The maximum number of bytes in a message.
+    pub fn max_record_size(&self) -> usize {
+        self.session.max_record_size()
+    }
+
+    /// The number of records opened so far.
+    pub fn sequence(&self) -> u64 {
+        self.session.sequence()
+    }
+
+    /// Whether or not a record has failed to authenticate, making the receiver unusable.
+    pub fn is_poisoned(&self) -> bool {
+        self.session.is_poisoned()
+    }
+
+    /// Open a record, writing out the message, and returning its length.
+    pub fn open_into(&mut self, record: &[u8], message: &mut [u8]) -> Result<usize, SessionError> {
+        self.session.open_into(record, message)
+    }
+
+    /// Open a record, returning the message.
+    #[cfg(feature = "alloc")]
+    pub fn open(&mut self, record: &[u8]) -> Result<Vec<u8>, SessionError> {
+        self.session.open(record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread};
+
+    use super::*;
+    use crate::test::keyed;
+
+    #[test]
+    fn test_halves_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Sender>();
+        assert_send::<Receiver>();
+    }
+
+    #[test]
+    fn test_directions_are_separated() {
+        let (mut i_sender, mut i_receiver) = keyed().split(Role::Initiator);
+        let (mut r_sender, _) = keyed().split(Role::Responder);
+        let mut i_record = [0u8; 32];
+        let mut r_record = [0u8; 32];
+        let len = i_sender.seal_into(b"hello", &mut i_record).unwrap();
+        r_sender.seal_into(b"hello", &mut r_record).unwrap();
+        assert_ne!(i_record, r_record);
+        // Our own records can't be opened as if they came from the other party.
+        let mut out = [0u8; 32];
+        assert_eq!(
+            i_receiver.open_into(&i_record[..len], &mut out),
+            Err(SessionError::Mac)
+        );
+        assert!(i_receiver.is_poisoned());
+        assert!(i_sender.seal_into(b"still fine", &mut i_record).is_ok());
+    }
+
+    #[test]
+    fn test_roles_before_splitting_dont_matter() {
+        let mut initiator = keyed();
+        let mut responder = keyed();
+        initiator.send_clr(b"hello", false);
+        responder.recv_clr(b"hello", false);
+        let (mut sender, _) = initiator.split(Role::Responder);
+        let (_, mut receiver) = responder.split(Role::Initiator);
+        let mut record = [0u8; 32];
+        let len = sender.seal_into(b"hello", &mut record).unwrap();
+        let mut out = [0u8; 32];
+        assert_eq!(receiver.open_into(&record[..len], &mut out), Ok(5));
+    }
+
+    #[test]
+    fn test_full_duplex_across_threads() {
+        let (mut client_sender, mut client_receiver) = keyed().split(Role::Initiator);
+        let (mut server_sender, mut server_receiver) = keyed().split(Role::Responder);
+        let (to_server, from_client) = mpsc::channel::<([u8; 64], usize)>();
+        let (to_client, from_server) = mpsc::channel::<([u8; 64], usize)>();
+
+        let threads = [
+            thread::spawn(move || {
+                for i in 0..100u8 {
+                    let mut record = [0u8; 64];
+                    let len = client_sender.seal_into(&[i; 8], &mut record).unwrap();
+                    to_server.send((record, len)).unwrap();
+                }
+            }),
+            thread::spawn(move || {
+                for i in 0..100u8 {
+                    let mut record = [0u8; 64];
+                    let len = server_sender.seal_into(&[i; 16], &mut record).unwrap();
+                    to_client.send((record, len)).unwrap();
+                }
+            }),
+            thread::spawn(move || {
+                for i in 0..100u8 {
+                    let (record, len) = from_client.recv().unwrap();
+                    let mut out = [0u8; 64];
+                    let out_len = server_receiver.open_into(&record[..len], &mut out).unwrap();
+                    assert_eq!(out[..out_len], [i; 8]);
+                }
+            }),
+            thread::spawn(move || {
+                for i in 0..100u8 {
+                    let (record, len) = from_server.recv().unwrap();
+                    let mut out = [0u8; 64];
+                    let out_len = client_receiver.open_into(&record[..len], &mut out).unwrap();
+                    assert_eq!(out[..out_len], [i; 16]);
+                }
+            }),
+        ];
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_undecided_role_panics() {
+        keyed().split(Role::Undecided);
+    }
+}
@@ -0,0 +1,329 @@
+use core::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::{
+    kitten::{KittenState, STATE_SIZE_U8},
+    meow::{
+        Flags, Meow, Role, Security, SupportedSecurity, FLAG_A, FLAG_C, FLAG_I, FLAG_K, FLAG_M,
+        FLAG_T,
+    },
+    permutation::Permutation,
+};
+
+/// The version of the export format, which is the first byte of an exported state.
+const EXPORT_VERSION: u8 = 1;
+/// The number of bytes used to identify the permutation of an exported state.
+const FINGERPRINT_LEN: usize = 8;
+/// The number of bytes in the tag of an encrypted export.
+const TAG_LEN: usize = 32;
+/// All of the flags an operation can use.
+const ALL_FLAGS: Flags = FLAG_I | FLAG_A | FLAG_C | FLAG_T | FLAG_M | FLAG_K;
+
+/// The number of bytes in an exported state.
+///
+/// The format is:
+///
+/// - 1 byte: the version of the format, currently 1.
+/// - 1 byte: the rate of the state, which identifies the security level.
+/// - 8 bytes: a fingerprint of the permutation.
+/// - 200 bytes: the permutation state.
+/// - 1 byte each: the position, beginning position, role, and current flags.
+pub const EXPORT_LEN: usize = 2 + FINGERPRINT_LEN + STATE_SIZE_U8 + 4;
+/// The number of bytes in an encrypted export.
+///
+/// This is an exported state, encrypted, followed by a 32 byte tag.
+pub const ENCRYPTED_EXPORT_LEN: usize = EXPORT_LEN + TAG_LEN;
+
+/// An error produced when importing a state fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportError {
+    /// The state was exported with an unknown version of the format.
+    Version,
+    /// The state was exported with a different security level.
+    Security,
+    /// The state was exported with a different permutation.
+    Permutation,
+    /// The position in the state is invalid.
+    Position,
+    /// The role is invalid.
+    Role,
+    /// The flags of the current operation are invalid.
+    Flags,
+    /// An encrypted state failed to decrypt, because of the wrong key, or tampering.
+    Decryption,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Version => write!(f, "Unknown export format version."),
+            ImportError::Security => write!(f, "State has the wrong security level."),
+            ImportError::Permutation => write!(f, "State uses the wrong permutation."),
+            ImportError::Position => write!(f, "State has an invalid position."),
+            ImportError::Role => write!(f, "State has an invalid role."),
+            ImportError::Flags => write!(f, "State has invalid flags."),
+            ImportError::Decryption => write!(f, "State failed to decrypt."),
+        }
+    }
+}
+
+impl core::error::Error for ImportError {}
+
+/// Identify a permutation, by applying it to the zero state.
+fn fingerprint<P: Permutation>() -> [u8; FINGERPRINT_LEN] {
+    let mut state = KittenState::<P>::new();
+    state.permute();
+    let mut out = [0u8; FINGERPRINT_LEN];
+    state.read(0, &mut out);
+    out
+}
+
+/// Create the state used to encrypt exports.
+fn export_cipher(key: &[u8]) -> Meow {
+    let mut meow = Meow::new(b"ck-meow state export");
+    meow.key(key, false);
+    meow
+}
+
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// Export this state, so that it can be restored later.
+    ///
+    /// The exported state contains everything needed to continue the protocol,
+    /// including any keys absorbed so far, so it needs to be stored securely.
+    /// The buffer gets zeroed when dropped. See [`Self::export_encrypted`] for
+    /// a version which can be stored in the open.
+    ///
+    /// The format is versioned, and described in [`EXPORT_LEN`].
+    ///
+    /// ```
+    /// use ck_meow::Meow;
+    ///
+    /// let mut meow = Meow::new(b"my protocol");
+    /// meow.ad(b"some data", false);
+    /// let exported = meow.export();
+    ///
+    /// let mut restored: Meow = Meow::import(&exported).unwrap();
+    /// restored.ad(b"more data", false);
+    /// ```
+    pub fn export(&self) -> Zeroizing<[u8; EXPORT_LEN]> {
+        let mut out = Zeroizing::new([0u8; EXPORT_LEN]);
+        out[0] = EXPORT_VERSION;
+        out[1] = Self::R;
+        out[2..2 + FINGERPRINT_LEN].copy_from_slice(&fingerprint::<P>());
+        let (state, rest) = out[2 + FINGERPRINT_LEN..].split_at_mut(STATE_SIZE_U8);
+        self.state.read(0, state);
+        rest.copy_from_slice(&[self.pos, self.pos_begin, self.role as u8, self.cur_flags]);
+        out
+    }
+
+    /// Restore a state exported with [`Self::export`].
+    ///
+    /// This checks every field of the exported state, and will fail if it
+    /// was exported with a different security level or permutation.
+    pub fn import(data: &[u8; EXPORT_LEN]) -> Result<Self, ImportError> {
+        if data[0] != EXPORT_VERSION {
+            return Err(ImportError::Version);
+        }
+        if data[1] != Self::R {
+            return Err(ImportError::Security);
+        }
+        if data[2..2 + FINGERPRINT_LEN] != fingerprint::<P>() {
+            return Err(ImportError::Permutation);
+        }
+        let (state_bytes, rest) = data[2 + FINGERPRINT_LEN..].split_at(STATE_SIZE_U8);
+        let (pos, pos_begin, role, cur_flags) = (rest[0], rest[1], rest[2], rest[3]);
+        // The position always stays inside of the rate, and operations
+        // always begin before the current position.
+        if pos >= Self::R || pos_begin > pos {
+            return Err(ImportError::Position);
+        }
+        let role = match role {
+            0 => Role::Initiator,
+            1 => Role::Responder,
+            2 => Role::Undecided,
+            _ => return Err(ImportError::Role),
+        };
+        if cur_flags & !ALL_FLAGS != 0 {
+            return Err(ImportError::Flags);
+        }
+        // Starting a transport operation always decides our role.
+        if cur_flags & FLAG_T != 0 && role == Role::Undecided {
+            return Err(ImportError::Role);
+        }
+        let mut state = KittenState::new();
+        state.write(0, state_bytes);
+        Ok(Self {
+            state,
+            pos,
+            pos_begin,
+            role,
+            cur_flags,
+            #[cfg(feature = "trace")]
+            trace: Default::default(),
+        })
+    }
+
+    /// Export this state, encrypted under a key.
+    ///
+    /// The result can be stored in the open, and can only be restored with
+    /// the same key, using [`Self::import_encrypted`]. Any tampering with
+    /// the result will be detected.
+    ///
+    /// The encryption is deterministic, so exporting the same state twice
+    /// will produce the same result, but nothing else is revealed.
+    ///
+    /// ```
+    /// use ck_meow::Meow;
+    ///
+    /// let key = [0xAA; 32];
+    /// let mut meow = Meow::new(b"my protocol");
+    /// meow.key(b"a secret", false);
+    /// let exported = meow.export_encrypted(&key);
+    ///
+    /// let restored: Meow = Meow::import_encrypted(&key, &exported).unwrap();
+    /// assert!(Meow::<128>::import_encrypted(&[0xBB; 32], &exported).is_err());
+    /// ```
+    pub fn export_encrypted(&self, key: &[u8]) -> [u8; ENCRYPTED_EXPORT_LEN] {
+        let plaintext = self.export();
+        let mut out = [0u8; ENCRYPTED_EXPORT_LEN];
+        let (ciphertext, tag) = out.split_at_mut(EXPORT_LEN);
+        // We use the tag as a synthetic nonce, which is why the encryption is deterministic.
+        let mut cipher = export_cipher(key);
+        let mut mac = cipher.clone();
+        mac.ad(&plaintext[..], false);
+        mac.prf(tag, false);
+        cipher.ad(tag, false);
+        ciphertext.copy_from_slice(&plaintext[..]);
+        cipher.send_enc(ciphertext, false);
+        out
+    }
+
+    /// Restore a state exported with [`Self::export_encrypted`].
+    ///
+    /// This fails if the key is wrong, or if the data has been tampered with,
+    /// along with all of the checks done by [`Self::import`].
+    pub fn import_encrypted(
+        key: &[u8],
+        data: &[u8; ENCRYPTED_EXPORT_LEN],
+    ) -> Result<Self, ImportError> {
+        let (ciphertext, tag) = data.split_at(EXPORT_LEN);
+        let mut cipher = export_cipher(key);
+        let mut mac = cipher.clone();
+        cipher.ad(tag, false);
+        let mut plaintext = Zeroizing::new([0u8; EXPORT_LEN]);
+        plaintext.copy_from_slice(ciphertext);
+        cipher.recv_enc(&mut plaintext[..], false);
+        mac.ad(&plaintext[..], false);
+        let mut expected = [0u8; TAG_LEN];
+        mac.prf(&mut expected, false);
+        if !bool::from(expected.ct_eq(tag)) {
+            return Err(ImportError::Decryption);
+        }
+        Self::import(&plaintext)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{KeccakF1600, KitTen};
+
+    fn transcript<const SECURITY: usize, P: Permutation>(meow: &mut Meow<SECURITY, P>) -> [u8; 32]
+    where
+        Security<SECURITY>: SupportedSecurity,
+    {
+        let mut data = *b"some data to encrypt";
+        meow.send_enc(&mut data, false);
+        meow.ad(b"more data", false);
+        let mut out = [0u8; 32];
+        meow.prf(&mut out, false);
+        out
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut meow = Meow::new(b"export test");
+        meow.key(b"key", false);
+        meow.ad(b"partial", false);
+        meow.send_clr(b"sets the role", false);
+
+        let mut restored = Meow::<128>::import(&meow.export()).unwrap();
+        assert_eq!(restored.state_bytes(), meow.state_bytes());
+        assert_eq!(transcript(&mut restored), transcript(&mut meow));
+    }
+
+    #[test]
+    fn test_export_continues_streaming() {
+        let mut meow0 = Meow::new(b"export test");
+        meow0.ad(b"hello ", false);
+        let mut meow1 = Meow::<128>::import(&meow0.export()).unwrap();
+        meow0.ad(b"world", true);
+        meow1.ad(b"world", true);
+        assert_eq!(transcript(&mut meow0), transcript(&mut meow1));
+    }
+
+    #[test]
+    fn test_import_rejects_wrong_parameters() {
+        let exported = Meow::new(b"export test").export();
+        assert_eq!(
+            Meow::<256>::import(&exported).err(),
+            Some(ImportError::Security)
+        );
+        assert_eq!(
+            Meow::<128, KeccakF1600>::import(&exported).err(),
+            Some(ImportError::Permutation)
+        );
+        assert!(Meow::<128, KitTen>::import(&exported).is_ok());
+    }
+
+    #[test]
+    fn test_import_validates_fields() {
+        let exported = Meow::new(b"export test").export();
+        let tail = EXPORT_LEN - 4;
+        let cases = [
+            (0, 2, ImportError::Version),
+            (tail, 166, ImportError::Position),
+            (tail + 1, 100, ImportError::Position),
+            (tail + 2, 3, ImportError::Role),
+            (tail + 3, 0x40, ImportError::Flags),
+            (tail + 3, FLAG_T, ImportError::Role),
+        ];
+        for (i, value, error) in cases {
+            let mut bad = exported.clone();
+            bad[i] = value;
+            assert_eq!(Meow::<128>::import(&bad).err(), Some(error));
+        }
+    }
+
+    #[test]
+    fn test_encrypted_export() {
+        let key = b"export key";
+        let mut meow = Meow::new(b"export test");
+        meow.key(b"secret", false);
+        let exported = meow.export_encrypted(key);
+        // The state shouldn't appear in the clear.
+        assert_ne!(exported[..EXPORT_LEN], meow.export()[..]);
+
+        let mut restored = Meow::<128>::import_encrypted(key, &exported).unwrap();
+        assert_eq!(transcript(&mut restored), transcript(&mut meow));
+
+        assert_eq!(
+            Meow::<128>::import_encrypted(b"wrong key", &exported).err(),
+            Some(ImportError::Decryption)
+        );
+        for i in [0, 100, EXPORT_LEN - 1, ENCRYPTED_EXPORT_LEN - 1] {
+            let mut bad = exported;
+            bad[i] ^= 1;
+            assert_eq!(
+                Meow::<128>::import_encrypted(key, &bad).err(),
+                Some(ImportError::Decryption)
+            );
+        }
+    }
+}
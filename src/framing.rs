@@ -0,0 +1,9 @@
+use crate::meow::Meow;
+
+/// Absorb some data as metadata, prefixed with its length.
+///
+/// This makes it impossible to confuse the boundaries between two inputs.
+pub(crate) fn frame(meow: &mut Meow, data: &[u8]) {
+    meow.meta_ad(&(data.len() as u64).to_le_bytes(), false);
+    meow.meta_ad(data, true);
+}
@@ -0,0 +1,677 @@
+use core::fmt;
+
+use rand_core::{CryptoRng, RngCore};
+use x25519_dalek::{PublicKey, ReusableSecret, SharedSecret, StaticSecret};
+
+use crate::meow::Meow;
+
+/// The number of bytes in an X25519 public key.
+const KEY_LEN: usize = 32;
+/// The number of bytes in the MAC ending each handshake message, once keyed.
+pub const HANDSHAKE_MAC_LEN: usize = 16;
+/// The number of bytes in the handshake hash.
+pub const HANDSHAKE_HASH_LEN: usize = 32;
+
+/// An error produced during a handshake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HandshakeError {
+    /// A static key required by the pattern is missing.
+    MissingKey,
+    /// It isn't our turn to write, or to read, a message.
+    Turn,
+    /// A buffer is too small, or a message has the wrong length.
+    Length,
+    /// A Diffie-Hellman exchange produced no shared secret, because of a bad public key.
+    Dh,
+    /// A message failed to authenticate, because of the wrong keys, or tampering.
+    Mac,
+    /// A previous call failed, so the handshake can't continue.
+    Poisoned,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::MissingKey => write!(f, "Handshake is missing a static key."),
+            HandshakeError::Turn => write!(f, "Not our turn in the handshake."),
+            HandshakeError::Length => write!(f, "Handshake message has the wrong length."),
+            HandshakeError::Dh => write!(f, "Diffie-Hellman produced no shared secret."),
+            HandshakeError::Mac => write!(f, "Handshake message failed to authenticate."),
+            HandshakeError::Poisoned => write!(f, "Handshake was poisoned by a failed call."),
+        }
+    }
+}
+
+impl core::error::Error for HandshakeError {}
+
+/// A handshake pattern, as defined by the Noise protocol framework.
+///
+/// The letters describe how each party's static key is handled:
+///
+/// - `N`: there is no static key.
+/// - `K`: the static key is known to the other party in advance.
+/// - `X`: the static key is transmitted during the handshake.
+/// - `I`: the static key is transmitted immediately, in the first message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Neither party has a static key.
+    NN,
+    /// The initiator knows the responder's static key in advance.
+    NK,
+    /// Both parties know each other's static key in advance.
+    KK,
+    /// Both parties transmit their static keys.
+    XX,
+    /// The initiator knows the responder's static key, and transmits its own immediately.
+    IK,
+}
+
+/// A single step of a handshake message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+    SS,
+}
+
+impl Pattern {
+    fn name(self) -> &'static [u8] {
+        match self {
+            Pattern::NN => b"Noise_NN_25519_ck-meow",
+            Pattern::NK => b"Noise_NK_25519_ck-meow",
+            Pattern::KK => b"Noise_KK_25519_ck-meow",
+            Pattern::XX => b"Noise_XX_25519_ck-meow",
+            Pattern::IK => b"Noise_IK_25519_ck-meow",
+        }
+    }
+
+    /// Whether the static keys of the initiator, and responder, are known in advance.
+    fn pre_messages(self) -> (bool, bool) {
+        match self {
+            Pattern::NN | Pattern::XX => (false, false),
+            Pattern::NK | Pattern::IK => (false, true),
+            Pattern::KK => (true, true),
+        }
+    }
+
+    /// Whether the initiator, and the responder, have a static key.
+    fn has_static(self) -> (bool, bool) {
+        match self {
+            Pattern::NN => (false, false),
+            Pattern::NK => (false, true),
+            Pattern::KK | Pattern::XX | Pattern::IK => (true, true),
+        }
+    }
+
+    fn messages(self) -> &'static [&'static [Token]] {
+        use Token::*;
+        match self {
+            Pattern::NN => &[&[E], &[E, EE]],
+            Pattern::NK => &[&[E, ES], &[E, EE]],
+            Pattern::KK => &[&[E, ES, SS], &[E, EE, SE]],
+            Pattern::XX => &[&[E], &[E, EE, S, ES], &[S, SE]],
+            Pattern::IK => &[&[E, ES, S, SS], &[E, EE, SE]],
+        }
+    }
+}
+
+/// The result of a completed handshake.
+pub struct HandshakeOutput {
+    /// The state after the handshake, keyed with all of the shared secrets.
+    ///
+    /// This can be used directly to exchange messages, with `send_enc` and
+    /// `send_mac`, and their counterparts, with both parties taking turns.
+    pub transport: Meow,
+    /// A hash of the entire handshake, which can be used for channel binding.
+    ///
+    /// Both parties get the same hash.
+    pub handshake_hash: [u8; HANDSHAKE_HASH_LEN],
+    /// The static key of the other party, if they have one.
+    pub remote_static: Option<PublicKey>,
+}
+
+/// A Noise-style handshake, with the symmetric state being a single Meow instance.
+///
+/// This works like Disco: the results of each Diffie-Hellman exchange are
+/// absorbed with `key`, ephemeral keys are sent with `send_clr`, static
+/// keys with `send_enc`, and each message ends with a MAC, using `send_mac`,
+/// once some shared secret has been absorbed. Payloads are sent with
+/// `send_enc` along with each message.
+///
+/// ```
+/// use ck_meow::{Handshake, Pattern};
+/// use rand_core::{CryptoRng, RngCore};
+/// use x25519_dalek::{PublicKey, StaticSecret};
+///
+/// fn example(rng: &mut (impl RngCore + CryptoRng)) {
+///     let server_key = StaticSecret::random_from_rng(&mut *rng);
+///     let server_public = PublicKey::from(&server_key);
+///
+///     let mut client =
+///         Handshake::initiator(Pattern::NK, b"my protocol", None, Some(server_public)).unwrap();
+///     let mut server = Handshake::responder(Pattern::NK, b"my protocol", Some(server_key), None)
+///         .unwrap();
+///
+///     let mut message = [0u8; 128];
+///     let mut payload = [0u8; 128];
+///     let len = client.write_message(b"hello", &mut message, rng).unwrap();
+///     let payload_len = server.read_message(&message[..len], &mut payload).unwrap();
+///     assert_eq!(&payload[..payload_len], b"hello");
+///
+///     let len = server.write_message(b"", &mut message, rng).unwrap();
+///     client.read_message(&message[..len], &mut payload).unwrap();
+///
+///     let client = client.finish().unwrap();
+///     let server = server.finish().unwrap();
+///     assert_eq!(client.handshake_hash, server.handshake_hash);
+/// }
+/// ```
+pub struct Handshake {
+    meow: Meow,
+    pattern: Pattern,
+    initiator: bool,
+    /// The index of the next message in the pattern.
+    message: usize,
+    /// Whether or not any shared secret has been absorbed.
+    keyed: bool,
+    s: Option<StaticSecret>,
+    e: Option<ReusableSecret>,
+    rs: Option<PublicKey>,
+    re: Option<PublicKey>,
+    /// Whether or not a call has failed, making the handshake unusable.
+    poisoned: bool,
+}
+
+impl Handshake {
+    /// Start a handshake as the initiator, who sends the first message.
+    ///
+    /// The prologue is data both parties need to agree on, but which isn't sent.
+    /// The static keys need to be provided when the pattern requires them.
+    pub fn initiator(
+        pattern: Pattern,
+        prologue: &[u8],
+        local_static: Option<StaticSecret>,
+        remote_static: Option<PublicKey>,
+    ) -> Result<Self, HandshakeError> {
+        Self::new(pattern, true, prologue, local_static, remote_static)
+    }
+
+    /// Start a handshake as the responder, who receives the first message.
+    ///
+    /// See [`Self::initiator`].
+    pub fn responder(
+        pattern: Pattern,
+        prologue: &[u8],
+        local_static: Option<StaticSecret>,
+        remote_static: Option<PublicKey>,
+    ) -> Result<Self, HandshakeError> {
+        Self::new(pattern, false, prologue, local_static, remote_static)
+    }
+
+    fn new(
+        pattern: Pattern,
+        initiator: bool,
+        prologue: &[u8],
+        s: Option<StaticSecret>,
+        rs: Option<PublicKey>,
+    ) -> Result<Self, HandshakeError> {
+        let (initiator_static, responder_static) = pattern.has_static();
+        let (initiator_pre, responder_pre) = pattern.pre_messages();
+        let (need_s, need_rs) = if initiator {
+            (initiator_static, responder_pre)
+        } else {
+            (responder_static, initiator_pre)
+        };
+        if (need_s && s.is_none()) || (need_rs && rs.is_none()) {
+            return Err(HandshakeError::MissingKey);
+        }
+        let s = s.filter(|_| need_s);
+        let rs = rs.filter(|_| need_rs);
+
+        let mut meow = Meow::new(pattern.name());
+        meow.ad(prologue, false);
+        let local = s.as_ref().map(PublicKey::from);
+        let (initiator_key, responder_key) = if initiator { (local, rs) } else { (rs, local) };
+        if let (true, Some(key)) = (initiator_pre, initiator_key) {
+            meow.ad(key.as_bytes(), false);
+        }
+        if let (true, Some(key)) = (responder_pre, responder_key) {
+            meow.ad(key.as_bytes(), false);
+        }
+        Ok(Self {
+            meow,
+            pattern,
+            initiator,
+            message: 0,
+            keyed: false,
+            s,
+            e: None,
+            rs,
+            re: None,
+            poisoned: false,
+        })
+    }
+
+    /// Whether or not all of the handshake messages have been exchanged.
+    pub fn is_finished(&self) -> bool {
+        self.message == self.pattern.messages().len()
+    }
+
+    /// Whether or not a call has failed, making the handshake unusable.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Run a step of the handshake, poisoning it if the step fails.
+    ///
+    /// A failed step may have already absorbed part of a message, so
+    /// the state can't be trusted to match the other party's anymore.
+    fn step<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, HandshakeError>,
+    ) -> Result<T, HandshakeError> {
+        if self.poisoned {
+            return Err(HandshakeError::Poisoned);
+        }
+        let result = f(self);
+        self.poisoned = result.is_err();
+        result
+    }
+
+    /// Check that the next message is ours to write, or to read, returning its tokens.
+    fn next_tokens(&self, writing: bool) -> Result<&'static [Token], HandshakeError> {
+        let tokens = self
+            .pattern
+            .messages()
+            .get(self.message)
+            .ok_or(HandshakeError::Turn)?;
+        // The initiator writes the even messages, and the responder the odd ones.
+        if (self.message % 2 == 0) != (self.initiator == writing) {
+            return Err(HandshakeError::Turn);
+        }
+        Ok(tokens)
+    }
+
+    /// The number of bytes taken by the tokens of a message, and the MAC.
+    fn overhead(&self, tokens: &[Token]) -> usize {
+        let mut keyed = self.keyed;
+        let mut out = 0;
+        for token in tokens {
+            match token {
+                Token::E | Token::S => out += KEY_LEN,
+                _ => keyed = true,
+            }
+        }
+        if keyed {
+            out += HANDSHAKE_MAC_LEN;
+        }
+        out
+    }
+
+    /// Absorb the result of a Diffie-Hellman exchange.
+    fn mix_dh(&mut self, token: Token) -> Result<(), HandshakeError> {
+        let missing = HandshakeError::MissingKey;
+        let e = self.e.as_ref().ok_or(missing);
+        let s = self.s.as_ref().ok_or(missing);
+        let re = self.re.as_ref().ok_or(missing);
+        let rs = self.rs.as_ref().ok_or(missing);
+        let shared: SharedSecret = match (token, self.initiator) {
+            (Token::EE, _) => e?.diffie_hellman(re?),
+            (Token::ES, true) | (Token::SE, false) => e?.diffie_hellman(rs?),
+            (Token::ES, false) | (Token::SE, true) => s?.diffie_hellman(re?),
+            (Token::SS, _) => s?.diffie_hellman(rs?),
+            (Token::E | Token::S, _) => unreachable!("not a Diffie-Hellman token"),
+        };
+        if !shared.was_contributory() {
+            return Err(HandshakeError::Dh);
+        }
+        self.meow.key(shared.as_bytes(), false);
+        self.keyed = true;
+        Ok(())
+    }
+
+    /// Write the next handshake message, along with a payload, returning its length.
+    ///
+    /// The randomness is used to generate our ephemeral key.
+    /// The payload is only encrypted once some shared secret has been absorbed,
+    /// which isn't the case for the first message of some patterns.
+    ///
+    /// If this fails, the handshake is poisoned, and every later call fails.
+    pub fn write_message<R: RngCore + CryptoRng>(
+        &mut self,
+        payload: &[u8],
+        out: &mut [u8],
+        rng: &mut R,
+    ) -> Result<usize, HandshakeError> {
+        self.step(|this| this.write_next(payload, out, rng))
+    }
+
+    fn write_next<R: RngCore + CryptoRng>(
+        &mut self,
+        payload: &[u8],
+        out: &mut [u8],
+        rng: &mut R,
+    ) -> Result<usize, HandshakeError> {
+        let tokens = self.next_tokens(true)?;
+        let len = self.overhead(tokens) + payload.len();
+        if out.len() < len {
+            return Err(HandshakeError::Length);
+        }
+        let mut pos = 0;
+        for &token in tokens {
+            match token {
+                Token::E => {
+                    let e = ReusableSecret::random_from_rng(&mut *rng);
+                    let key = &mut out[pos..pos + KEY_LEN];
+                    key.copy_from_slice(PublicKey::from(&e).as_bytes());
+                    self.meow.send_clr(key, false);
+                    self.e = Some(e);
+                    pos += KEY_LEN;
+                }
+                Token::S => {
+                    let s = self.s.as_ref().ok_or(HandshakeError::MissingKey)?;
+                    let key = &mut out[pos..pos + KEY_LEN];
+                    key.copy_from_slice(PublicKey::from(s).as_bytes());
+                    self.meow.send_enc(key, false);
+                    pos += KEY_LEN;
+                }
+                _ => self.mix_dh(token)?,
+            }
+        }
+        let body = &mut out[pos..pos + payload.len()];
+        body.copy_from_slice(payload);
+        if self.keyed {
+            self.meow.send_enc(body, false);
+            self.meow.send_mac(&mut out[len - HANDSHAKE_MAC_LEN..len]);
+        } else {
+            self.meow.send_clr(body, false);
+        }
+        self.message += 1;
+        Ok(len)
+    }
+
+    /// Read the next handshake message, writing its payload out, and returning its length.
+    ///
+    /// If this fails, the handshake is poisoned, and every later call fails.
+    pub fn read_message(
+        &mut self,
+        message: &[u8],
+        payload: &mut [u8],
+    ) -> Result<usize, HandshakeError> {
+        self.step(|this| this.read_next(message, payload))
+    }
+
+    fn read_next(&mut self, message: &[u8], payload: &mut [u8]) -> Result<usize, HandshakeError> {
+        let tokens = self.next_tokens(false)?;
+        let overhead = self.overhead(tokens);
+        let payload_len = message
+            .len()
+            .checked_sub(overhead)
+            .ok_or(HandshakeError::Length)?;
+        if payload.len() < payload_len {
+            return Err(HandshakeError::Length);
+        }
+        let mut pos = 0;
+        for &token in tokens {
+            match token {
+                Token::E => {
+                    let mut key = [0u8; KEY_LEN];
+                    key.copy_from_slice(&message[pos..pos + KEY_LEN]);
+                    self.meow.recv_clr(&key, false);
+                    self.re = Some(PublicKey::from(key));
+                    pos += KEY_LEN;
+                }
+                Token::S => {
+                    let mut key = [0u8; KEY_LEN];
+                    key.copy_from_slice(&message[pos..pos + KEY_LEN]);
+                    self.meow.recv_enc(&mut key, false);
+                    self.rs = Some(PublicKey::from(key));
+                    pos += KEY_LEN;
+                }
+                _ => self.mix_dh(token)?,
+            }
+        }
+        let body = &mut payload[..payload_len];
+        body.copy_from_slice(&message[pos..pos + payload_len]);
+        if self.keyed {
+            self.meow.recv_enc(body, false);
+            let mut mac = [0u8; HANDSHAKE_MAC_LEN];
+            mac.copy_from_slice(&message[pos + payload_len..]);
+            if self.meow.recv_mac(&mut mac).is_err() {
+                body.fill(0);
+                return Err(HandshakeError::Mac);
+            }
+        } else {
+            self.meow.recv_clr(body, false);
+        }
+        self.message += 1;
+        Ok(payload_len)
+    }
+
+    /// Finish the handshake, once all of the messages have been exchanged.
+    pub fn finish(self) -> Result<HandshakeOutput, HandshakeError> {
+        if self.poisoned {
+            return Err(HandshakeError::Poisoned);
+        }
+        if !self.is_finished() {
+            return Err(HandshakeError::Turn);
+        }
+        let mut hash_state = self.meow.clone();
+        hash_state.meta_ad(b"handshake hash", false);
+        let mut handshake_hash = [0u8; HANDSHAKE_HASH_LEN];
+        hash_state.prf(&mut handshake_hash, false);
+        Ok(HandshakeOutput {
+            transport: self.meow,
+            handshake_hash,
+            remote_static: self.rs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::SeedableRng;
+
+    use super::*;
+    use crate::MeowRng;
+
+    struct Keys {
+        initiator: StaticSecret,
+        responder: StaticSecret,
+    }
+
+    fn keys(rng: &mut MeowRng) -> Keys {
+        Keys {
+            initiator: StaticSecret::random_from_rng(&mut *rng),
+            responder: StaticSecret::random_from_rng(&mut *rng),
+        }
+    }
+
+    fn start(pattern: Pattern, keys: &Keys) -> (Handshake, Handshake) {
+        let i_pub = PublicKey::from(&keys.initiator);
+        let r_pub = PublicKey::from(&keys.responder);
+        let initiator = Handshake::initiator(
+            pattern,
+            b"prologue",
+            Some(keys.initiator.clone()),
+            Some(r_pub),
+        )
+        .unwrap();
+        let responder = Handshake::responder(
+            pattern,
+            b"prologue",
+            Some(keys.responder.clone()),
+            Some(i_pub),
+        )
+        .unwrap();
+        (initiator, responder)
+    }
+
+    /// Run a handshake to completion, sending a payload with each message.
+    fn run(
+        mut initiator: Handshake,
+        mut responder: Handshake,
+        rng: &mut MeowRng,
+    ) -> Result<(HandshakeOutput, HandshakeOutput), HandshakeError> {
+        let mut message = [0u8; 256];
+        let mut payload = [0u8; 256];
+        let mut turn = 0usize;
+        while !initiator.is_finished() {
+            let (writer, reader) = if turn % 2 == 0 {
+                (&mut initiator, &mut responder)
+            } else {
+                (&mut responder, &mut initiator)
+            };
+            let sent = [turn as u8; 10];
+            let len = writer.write_message(&sent, &mut message, rng)?;
+            let payload_len = reader.read_message(&message[..len], &mut payload)?;
+            assert_eq!(payload[..payload_len], sent);
+            turn += 1;
+        }
+        assert!(responder.is_finished());
+        Ok((initiator.finish()?, responder.finish()?))
+    }
+
+    #[test]
+    fn test_all_patterns() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        let keys = keys(&mut rng);
+        for pattern in [
+            Pattern::NN,
+            Pattern::NK,
+            Pattern::KK,
+            Pattern::XX,
+            Pattern::IK,
+        ] {
+            let (initiator, responder) = start(pattern, &keys);
+            let (mut i_out, mut r_out) = run(initiator, responder, &mut rng).unwrap();
+            assert_eq!(i_out.handshake_hash, r_out.handshake_hash);
+
+            let (i_static, r_static) = pattern.has_static();
+            assert_eq!(
+                r_out.remote_static,
+                Some(PublicKey::from(&keys.initiator)).filter(|_| i_static)
+            );
+            assert_eq!(
+                i_out.remote_static,
+                Some(PublicKey::from(&keys.responder)).filter(|_| r_static)
+            );
+
+            let mut data = *b"transport data";
+            i_out.transport.send_enc(&mut data, false);
+            let mut mac = [0u8; 16];
+            i_out.transport.send_mac(&mut mac);
+            r_out.transport.recv_enc(&mut data, false);
+            assert!(r_out.transport.recv_mac(&mut mac).is_ok());
+            assert_eq!(&data, b"transport data");
+        }
+    }
+
+    #[test]
+    fn test_different_sessions_differ() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        let keys = keys(&mut rng);
+        let (i0, r0) = start(Pattern::XX, &keys);
+        let (i1, r1) = start(Pattern::XX, &keys);
+        let (out0, _) = run(i0, r0, &mut rng).unwrap();
+        let (out1, _) = run(i1, r1, &mut rng).unwrap();
+        assert_ne!(out0.handshake_hash, out1.handshake_hash);
+    }
+
+    #[test]
+    fn test_wrong_static_key_fails() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        let keys0 = keys(&mut rng);
+        let keys1 = keys(&mut rng);
+        let initiator = Handshake::initiator(
+            Pattern::NK,
+            b"prologue",
+            None,
+            Some(PublicKey::from(&keys1.responder)),
+        )
+        .unwrap();
+        let responder =
+            Handshake::responder(Pattern::NK, b"prologue", Some(keys0.responder), None).unwrap();
+        assert_eq!(
+            run(initiator, responder, &mut rng).err(),
+            Some(HandshakeError::Mac)
+        );
+    }
+
+    #[test]
+    fn test_prologue_mismatch_fails() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        let initiator = Handshake::initiator(Pattern::NN, b"one", None, None).unwrap();
+        let responder = Handshake::responder(Pattern::NN, b"two", None, None).unwrap();
+        assert_eq!(
+            run(initiator, responder, &mut rng).err(),
+            Some(HandshakeError::Mac)
+        );
+    }
+
+    #[test]
+    fn test_tampering_fails() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        let keys = keys(&mut rng);
+        let (mut initiator, mut responder) = start(Pattern::IK, &keys);
+        let mut message = [0u8; 256];
+        let mut payload = [0u8; 256];
+        let len = initiator
+            .write_message(b"payload", &mut message, &mut rng)
+            .unwrap();
+        for i in [0, KEY_LEN, len - 1] {
+            let mut responder = start(Pattern::IK, &keys).1;
+            let mut bad = message;
+            bad[i] ^= 1;
+            assert!(responder.read_message(&bad[..len], &mut payload).is_err());
+        }
+        assert_eq!(
+            responder.read_message(&message[..len - 1], &mut payload),
+            Err(HandshakeError::Mac)
+        );
+        // The failed message was partially absorbed, so retrying can't work.
+        assert!(responder.is_poisoned());
+        assert_eq!(
+            responder.read_message(&message[..len], &mut payload),
+            Err(HandshakeError::Poisoned)
+        );
+    }
+
+    #[test]
+    fn test_misuse() {
+        let mut rng = MeowRng::seed_from_u64(0);
+        assert!(matches!(
+            Handshake::initiator(Pattern::XX, b"", None, None),
+            Err(HandshakeError::MissingKey)
+        ));
+        assert!(matches!(
+            Handshake::initiator(Pattern::NK, b"", None, None),
+            Err(HandshakeError::MissingKey)
+        ));
+
+        let initiator = Handshake::initiator(Pattern::NN, b"", None, None).unwrap();
+        let mut responder = Handshake::responder(Pattern::NN, b"", None, None).unwrap();
+        let mut message = [0u8; 64];
+        assert_eq!(
+            responder.write_message(b"", &mut message, &mut rng),
+            Err(HandshakeError::Turn)
+        );
+        assert!(matches!(initiator.finish(), Err(HandshakeError::Turn)));
+
+        let mut initiator = Handshake::initiator(Pattern::NN, b"", None, None).unwrap();
+        assert_eq!(
+            initiator.write_message(b"", &mut message[..KEY_LEN - 1], &mut rng),
+            Err(HandshakeError::Length)
+        );
+        assert!(initiator.is_poisoned());
+        assert_eq!(
+            initiator.write_message(b"", &mut message, &mut rng),
+            Err(HandshakeError::Poisoned)
+        );
+        assert!(matches!(initiator.finish(), Err(HandshakeError::Poisoned)));
+    }
+}
@@ -0,0 +1,189 @@
+use digest::{
+    consts::U32, ExtendableOutput, ExtendableOutputReset, FixedOutput, FixedOutputReset,
+    HashMarker, Output, OutputSizeUser, Reset, Update, XofReader,
+};
+
+use crate::meow::Meow;
+
+/// The protocol string used by [`MeowHash`], by default.
+const HASH_PROTOCOL: &[u8] = b"ck-meow hash";
+
+/// A hash function built on top of Meow.
+///
+/// This implements the traits from the `digest` crate, producing 32 byte
+/// hashes, or an arbitrary amount of output, as an extendable output function.
+///
+/// Hashing a message is the same as absorbing it with `ad`, and then
+/// squeezing out the hash with `prf`:
+///
+/// ```
+/// use ck_meow::{Meow, MeowHash};
+/// use digest::Digest;
+///
+/// let hash = MeowHash::digest(b"big data");
+///
+/// let mut meow = Meow::new(b"ck-meow hash");
+/// meow.ad(b"big data", false);
+/// let mut expected = [0u8; 32];
+/// meow.prf(&mut expected, false);
+/// assert_eq!(hash[..], expected);
+/// ```
+///
+/// The output of the extendable output function starts with the fixed size hash.
+#[derive(Clone)]
+pub struct MeowHash {
+    /// The state, having started absorbing the message.
+    meow: Meow,
+    /// The state, before absorbing anything, used to reset.
+    initial: Meow,
+}
+
+impl MeowHash {
+    /// Create a hash function using a custom protocol string.
+    ///
+    /// Different protocol strings produce unrelated hash functions.
+    pub fn with_protocol(protocol: &[u8]) -> Self {
+        let initial = Meow::new(protocol);
+        let mut meow = initial.clone();
+        // We start an empty operation, so that all updates can continue it.
+        meow.ad(&[], false);
+        Self { meow, initial }
+    }
+}
+
+impl Default for MeowHash {
+    fn default() -> Self {
+        Self::with_protocol(HASH_PROTOCOL)
+    }
+}
+
+impl HashMarker for MeowHash {}
+
+impl OutputSizeUser for MeowHash {
+    type OutputSize = U32;
+}
+
+impl Update for MeowHash {
+    fn update(&mut self, data: &[u8]) {
+        self.meow.ad(data, true);
+    }
+}
+
+impl FixedOutput for MeowHash {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.meow.prf(out, false);
+    }
+}
+
+impl FixedOutputReset for MeowHash {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.meow.prf(out, false);
+        self.reset();
+    }
+}
+
+impl Reset for MeowHash {
+    fn reset(&mut self) {
+        self.meow = self.initial.clone();
+        self.meow.ad(&[], false);
+    }
+}
+
+impl ExtendableOutput for MeowHash {
+    type Reader = MeowXofReader;
+
+    fn finalize_xof(self) -> Self::Reader {
+        MeowXofReader {
+            meow: self.meow,
+            started: false,
+        }
+    }
+}
+
+impl ExtendableOutputReset for MeowHash {
+    fn finalize_xof_reset(&mut self) -> Self::Reader {
+        let reader = MeowXofReader {
+            meow: self.meow.clone(),
+            started: false,
+        };
+        self.reset();
+        reader
+    }
+}
+
+/// Reads the output of [`MeowHash`], as an extendable output function.
+///
+/// All of the reads together form a single `prf` operation.
+#[derive(Clone)]
+pub struct MeowXofReader {
+    meow: Meow,
+    started: bool,
+}
+
+impl XofReader for MeowXofReader {
+    fn read(&mut self, buffer: &mut [u8]) {
+        self.meow.prf(buffer, self.started);
+        self.started = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use digest::Digest;
+
+    fn expected(protocol: &[u8], data: &[u8], out: &mut [u8]) {
+        let mut meow = Meow::new(protocol);
+        meow.ad(data, false);
+        meow.prf(out, false);
+    }
+
+    #[test]
+    fn test_matches_meow() {
+        let data = [0xAB; 500];
+        for len in [0, 1, 100, 166, 500] {
+            let mut out = [0u8; 32];
+            expected(HASH_PROTOCOL, &data[..len], &mut out);
+            assert_eq!(MeowHash::digest(&data[..len])[..], out);
+
+            let mut hash = MeowHash::with_protocol(b"custom");
+            for chunk in data[..len].chunks(7) {
+                Update::update(&mut hash, chunk);
+            }
+            expected(b"custom", &data[..len], &mut out);
+            assert_eq!(hash.finalize_fixed()[..], out);
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut hash = MeowHash::default();
+        Update::update(&mut hash, b"some data");
+        let first = hash.finalize_fixed_reset();
+        Update::update(&mut hash, b"some data");
+        assert_eq!(hash.clone().finalize_fixed(), first);
+        Reset::reset(&mut hash);
+        assert_eq!(hash.finalize_fixed(), MeowHash::digest(b""));
+    }
+
+    #[test]
+    fn test_xof() {
+        let mut hash = MeowHash::default();
+        Update::update(&mut hash, b"some data");
+        let fixed = hash.clone().finalize_fixed();
+
+        let mut out = [0u8; 400];
+        hash.clone().finalize_xof().read(&mut out);
+        assert_eq!(out[..32], fixed[..]);
+        let mut expected_out = [0u8; 400];
+        expected(HASH_PROTOCOL, b"some data", &mut expected_out);
+        assert_eq!(out, expected_out);
+
+        let mut reader = hash.finalize_xof();
+        let mut chunked = [0u8; 400];
+        for chunk in chunked.chunks_mut(33) {
+            reader.read(chunk);
+        }
+        assert_eq!(chunked, out);
+    }
+}
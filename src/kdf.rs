@@ -0,0 +1,155 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+use crate::{framing::frame, meow::Meow};
+
+/// The protocol string used by [`MeowKdf`].
+const KDF_PROTOCOL: &[u8] = b"ck-meow KDF";
+
+/// A key derivation function built on top of Meow.
+///
+/// This follows the extract and expand interface of HKDF. First, some
+/// input keying material, along with an optional salt, gets extracted into
+/// a pseudorandom key. That key can then be expanded into many keys,
+/// each identified by a list of labels:
+///
+/// ```
+/// use ck_meow::MeowKdf;
+///
+/// let kdf = MeowKdf::extract(b"some salt", b"master secret");
+/// let encryption_key = kdf.expand::<32>(&[b"my app", b"encryption"]);
+/// let mac_key = kdf.expand::<32>(&[b"my app", b"mac"]);
+/// assert_ne!(encryption_key, mac_key);
+/// ```
+///
+/// Extracting absorbs the salt with `meta_ad`, and the keying material with `key`.
+/// Expanding then absorbs the number of labels, each label, and the output length
+/// with `meta_ad`, before squeezing out the key with `prf`.
+/// Each of these is prefixed with its length, so distinct lists of labels
+/// never produce the same key, even if they have the same concatenation.
+/// Because the output length is included, a shorter key is also never a prefix
+/// of a longer key with the same labels.
+#[derive(Clone)]
+pub struct MeowKdf {
+    /// The state, after absorbing the salt and keying material.
+    meow: Meow,
+}
+
+impl MeowKdf {
+    /// Extract a pseudorandom key from some input keying material, and a salt.
+    ///
+    /// The salt can be empty, but using a random salt, if available, is better.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Self {
+        let mut meow = Meow::new(KDF_PROTOCOL);
+        frame(&mut meow, salt);
+        meow.key(ikm, false);
+        Self { meow }
+    }
+
+    /// Expand the key into an output buffer, using a list of labels.
+    ///
+    /// The output can be of any length.
+    pub fn expand_into(&self, labels: &[&[u8]], out: &mut [u8]) {
+        let mut meow = self.meow.clone();
+        meow.meta_ad(&(labels.len() as u64).to_le_bytes(), false);
+        for label in labels {
+            frame(&mut meow, label);
+        }
+        meow.meta_ad(&(out.len() as u64).to_le_bytes(), false);
+        meow.prf(out, false);
+    }
+
+    /// Expand the key into a fixed size key, using a list of labels.
+    ///
+    /// The key gets zeroed when dropped.
+    pub fn expand<const N: usize>(&self, labels: &[&[u8]]) -> Zeroizing<[u8; N]> {
+        let mut out = Zeroizing::new([0u8; N]);
+        self.expand_into(labels, &mut out[..]);
+        out
+    }
+
+    /// Expand the key into a key of any length, using a list of labels.
+    ///
+    /// The key gets zeroed when dropped.
+    #[cfg(feature = "alloc")]
+    pub fn expand_vec(&self, labels: &[&[u8]], len: usize) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(alloc::vec![0u8; len]);
+        self.expand_into(labels, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let mut meow = Meow::new(KDF_PROTOCOL);
+        meow.meta_ad(&4u64.to_le_bytes(), false);
+        meow.meta_ad(b"salt", true);
+        meow.key(b"ikm", false);
+        meow.meta_ad(&2u64.to_le_bytes(), false);
+        meow.meta_ad(&1u64.to_le_bytes(), false);
+        meow.meta_ad(b"a", true);
+        meow.meta_ad(&2u64.to_le_bytes(), false);
+        meow.meta_ad(b"bc", true);
+        meow.meta_ad(&32u64.to_le_bytes(), false);
+        let mut expected = [0u8; 32];
+        meow.prf(&mut expected, false);
+
+        let kdf = MeowKdf::extract(b"salt", b"ikm");
+        assert_eq!(*kdf.expand::<32>(&[b"a", b"bc"]), expected);
+    }
+
+    #[test]
+    fn test_distinct_labels_differ() {
+        let kdf = MeowKdf::extract(b"", b"ikm");
+        let label_sets: [&[&[u8]]; 6] = [
+            &[],
+            &[b""],
+            &[b"", b""],
+            &[b"abc"],
+            &[b"a", b"bc"],
+            &[b"ab", b"c"],
+        ];
+        for (i, a) in label_sets.iter().enumerate() {
+            for b in &label_sets[i + 1..] {
+                assert_ne!(kdf.expand::<32>(a), kdf.expand::<32>(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_inputs_are_separated() {
+        let key = |salt: &[u8], ikm: &[u8]| *MeowKdf::extract(salt, ikm).expand::<32>(&[]);
+        assert_ne!(key(b"", b"ikm"), key(b"salt", b"ikm"));
+        assert_ne!(key(b"salt", b"ikm"), key(b"salt", b"other ikm"));
+        assert_ne!(key(b"ab", b"c"), key(b"a", b"bc"));
+    }
+
+    #[test]
+    fn test_length_is_bound() {
+        let kdf = MeowKdf::extract(b"salt", b"ikm");
+        let short = kdf.expand::<16>(&[b"label"]);
+        let long = kdf.expand::<32>(&[b"label"]);
+        assert_ne!(short[..], long[..16]);
+
+        let mut out = [0u8; 500];
+        kdf.expand_into(&[b"label"], &mut out);
+        let mut again = [0u8; 500];
+        kdf.expand_into(&[b"label"], &mut again);
+        assert_eq!(out, again);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_expand_vec() {
+        let kdf = MeowKdf::extract(b"salt", b"ikm");
+        assert_eq!(
+            kdf.expand_vec(&[b"label"], 32)[..],
+            kdf.expand::<32>(&[b"label"])[..]
+        );
+    }
+}
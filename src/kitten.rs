@@ -1,74 +1,309 @@
-use core::ops::{Deref, DerefMut};
+use core::marker::PhantomData;
 
 use zeroize::Zeroize;
 
+use crate::permutation::Permutation;
+
 /// The number of words in our permutation state.
 pub const STATE_SIZE_U64: usize = 25;
 /// The number of bytes in our permutation state.
 pub const STATE_SIZE_U8: usize = STATE_SIZE_U64 * 8;
 
-/// The Kit-Ten permutation.
-///
-/// This is a reduced round version of the keccak permutation.
-fn kitten(state: &mut [u64; STATE_SIZE_U64]) {
-    keccak::keccak_p(state, 10);
-}
-
-/// A buffer of bytes which is aligned, so that we can apply our permutation to it.
+/// The state our permutation operates on.
 ///
 /// Strobe wants to operate on individual bytes, whereas the kitten permutation
-/// wants to operate on 64 bit words. To reconcile the two, we need a buffer
-/// of bytes which is correctly aligned, so that it can be easily transmuted
-/// into a buffer of words, and
+/// wants to operate on 64 bit words. To reconcile the two, we store the state
+/// as words, and address its bytes in little endian order. In other words,
+/// byte `i` of the state is byte `i % 8` of word `i / 8`, in little endian order,
+/// no matter what the endianness of the platform is.
+///
+/// The state is generic over the permutation we apply to it.
 #[derive(Clone, Zeroize)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
-#[repr(align(8))]
-pub struct AlignedKittenState(pub [u8; STATE_SIZE_U8]);
+pub struct KittenState<P>([u64; STATE_SIZE_U64], PhantomData<P>);
+
+impl<P: Permutation> KittenState<P> {
+    /// Create a new state, with all bytes set to 0.
+    pub fn new() -> Self {
+        Self([0u64; STATE_SIZE_U64], PhantomData)
+    }
 
-impl AlignedKittenState {
-    /// Apply the kitten permutation to this state.
+    /// Apply the permutation to this state.
     pub fn permute(&mut self) {
-        // SAFETY: because we've declared this struct to have an alignment of 8,
-        // this transmutation will work
-        let state_u64 = unsafe { &mut *(self as *mut Self as *mut [u64; STATE_SIZE_U64]) };
-        // In placing bytes into the state, we've always assumed that the u64s
-        // are in little endian order. To correct for this on big endian architectures,
-        // we need to do the following:
-        for state in state_u64.iter_mut() {
-            *state = u64::from_le(*state);
+        P::permute(&mut self.0);
+    }
+
+    /// Apply the permutation to several states at once.
+    ///
+    /// Only the states which are present get permuted. Using this is faster than
+    /// permuting each state individually, since the permutation can process
+    /// all of the states in lockstep.
+    pub fn permute_many<const N: usize>(mut states: [Option<&mut Self>; N]) {
+        let mut words = [[0u64; STATE_SIZE_U64]; N];
+        for (words, state) in words.iter_mut().zip(&states) {
+            if let Some(state) = state {
+                *words = state.0;
+            }
         }
-        // Now we can safely permute.
-        kitten(state_u64);
-        // We also need to place the words back in little endian order.
-        for state in state_u64.iter_mut() {
-            *state = u64::to_le(*state);
+        P::permute_many(&mut words);
+        for (words, state) in words.iter().zip(&mut states) {
+            if let Some(state) = state {
+                state.0 = *words;
+            }
         }
+        words.zeroize();
     }
 }
 
-impl Deref for AlignedKittenState {
-    type Target = [u8; STATE_SIZE_U8];
+/// Read bytes into part of a word, starting at a given offset.
+///
+/// This returns the word, along with a mask of the bytes it covers.
+fn load_partial(offset: usize, data: &[u8]) -> (u64, u64) {
+    let mut word = [0u8; 8];
+    let mut mask = [0u8; 8];
+    word[offset..offset + data.len()].copy_from_slice(data);
+    mask[offset..offset + data.len()].fill(0xFF);
+    (u64::from_le_bytes(word), u64::from_le_bytes(mask))
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+/// Write part of a word into bytes, starting at a given offset.
+fn store_partial(offset: usize, word: u64, data: &mut [u8]) {
+    data.copy_from_slice(&word.to_le_bytes()[offset..offset + data.len()]);
 }
 
-impl DerefMut for AlignedKittenState {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+// These operations all work on a span of the state, starting at a given position.
+//
+// The caller is responsible for making sure that the span doesn't go past
+// the end of the state.
+impl<P> KittenState<P> {
+    /// Apply a function to each word covered by a span of the state.
+    ///
+    /// The function gets called with the word of the state, the data at that
+    /// position, as a word, and a mask of the bytes of that word that the span covers.
+    /// Only the unaligned edges of the span are ever partial words.
+    fn apply(&mut self, pos: usize, data: &[u8], mut f: impl FnMut(&mut u64, u64, u64)) {
+        let head = ((8 - pos % 8) % 8).min(data.len());
+        let (head, data) = data.split_at(head);
+        if !head.is_empty() {
+            let (word, mask) = load_partial(pos % 8, head);
+            f(&mut self.0[pos / 8], word, mask);
+        }
+        let start = (pos + head.len()) / 8;
+        let mut chunks = data.chunks_exact(8);
+        for (lane, chunk) in self.0[start..].iter_mut().zip(&mut chunks) {
+            f(lane, u64::from_le_bytes(chunk.try_into().unwrap()), !0);
+        }
+        let tail = chunks.remainder();
+        if !tail.is_empty() {
+            let (word, mask) = load_partial(0, tail);
+            f(&mut self.0[start + data.len() / 8], word, mask);
+        }
+    }
+
+    /// Like `apply`, except that the function also produces a word to write back into the data.
+    fn apply_mut(
+        &mut self,
+        pos: usize,
+        data: &mut [u8],
+        mut f: impl FnMut(&mut u64, u64, u64) -> u64,
+    ) {
+        let head = ((8 - pos % 8) % 8).min(data.len());
+        let (head, data) = data.split_at_mut(head);
+        if !head.is_empty() {
+            let (word, mask) = load_partial(pos % 8, head);
+            let out = f(&mut self.0[pos / 8], word, mask);
+            store_partial(pos % 8, out, head);
+        }
+        let start = (pos + head.len()) / 8;
+        let full = data.len() / 8;
+        let mut chunks = data.chunks_exact_mut(8);
+        for (lane, chunk) in self.0[start..].iter_mut().zip(&mut chunks) {
+            let out = f(lane, u64::from_le_bytes((&*chunk).try_into().unwrap()), !0);
+            chunk.copy_from_slice(&out.to_le_bytes());
+        }
+        let tail = chunks.into_remainder();
+        if !tail.is_empty() {
+            let (word, mask) = load_partial(0, tail);
+            let out = f(&mut self.0[start + full], word, mask);
+            store_partial(0, out, tail);
+        }
+    }
+
+    /// Xor data into the state.
+    pub fn xor_in(&mut self, pos: usize, data: &[u8]) {
+        self.apply(pos, data, |lane, word, _| *lane ^= word);
+    }
+
+    /// Xor a single byte into the state.
+    pub fn xor_byte(&mut self, pos: usize, byte: u8) {
+        self.0[pos / 8] ^= u64::from(byte) << (8 * (pos % 8));
+    }
+
+    /// Xor data into the state, and then set the data to the result.
+    pub fn xor_in_out(&mut self, pos: usize, data: &mut [u8]) {
+        self.apply_mut(pos, data, |lane, word, _| {
+            *lane ^= word;
+            *lane
+        });
+    }
+
+    /// Xor the state into the data, and then set the state to the original data.
+    pub fn exchange(&mut self, pos: usize, data: &mut [u8]) {
+        self.apply_mut(pos, data, |lane, word, mask| {
+            let out = *lane ^ word;
+            *lane = (*lane & !mask) | word;
+            out
+        });
+    }
+
+    /// Overwrite part of the state with data.
+    pub fn write(&mut self, pos: usize, data: &[u8]) {
+        self.apply(pos, data, |lane, word, mask| *lane = (*lane & !mask) | word);
+    }
+
+    /// Copy part of the state into a buffer.
+    pub fn read(&self, pos: usize, data: &mut [u8]) {
+        let head = ((8 - pos % 8) % 8).min(data.len());
+        let (head, data) = data.split_at_mut(head);
+        if !head.is_empty() {
+            store_partial(pos % 8, self.0[pos / 8], head);
+        }
+        let start = (pos + head.len()) / 8;
+        let full = data.len() / 8;
+        let mut chunks = data.chunks_exact_mut(8);
+        for (lane, chunk) in self.0[start..].iter().zip(&mut chunks) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        let tail = chunks.into_remainder();
+        if !tail.is_empty() {
+            store_partial(0, self.0[start + full], tail);
+        }
+    }
+
+    /// Copy part of the state into a buffer, zeroing out that part of the state.
+    pub fn take(&mut self, pos: usize, data: &mut [u8]) {
+        self.apply_mut(pos, data, |lane, _, mask| {
+            let out = *lane;
+            *lane &= !mask;
+            out
+        });
+    }
+
+    /// Set part of the state to zero.
+    pub fn zero(&mut self, pos: usize, len: usize) {
+        self.apply(pos, &[0u8; STATE_SIZE_U8][..len], |lane, _, mask| {
+            *lane &= !mask
+        });
+    }
+
+    /// Copy the entire state out as bytes.
+    #[cfg(test)]
+    pub fn to_bytes(&self) -> [u8; STATE_SIZE_U8] {
+        let mut out = [0u8; STATE_SIZE_U8];
+        for (word, bytes) in self.0.iter().zip(out.chunks_exact_mut(8)) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        out
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{AlignedKittenState, STATE_SIZE_U8};
+    use super::{KittenState, STATE_SIZE_U8};
+    use crate::permutation::{KeccakF1600, KitTen, TurboShake};
 
     #[test]
     fn test_permute_changes_state() {
-        let data0 = AlignedKittenState([0u8; STATE_SIZE_U8]);
+        let data0 = KittenState::<KitTen>::new();
         let mut data1 = data0.clone();
         data1.permute();
         assert_ne!(data0, data1);
     }
+
+    #[test]
+    fn test_permutations_differ() {
+        let mut data0 = KittenState::<KitTen>::new();
+        data0.permute();
+        let mut data1 = KittenState::<TurboShake>::new();
+        data1.permute();
+        let mut data2 = KittenState::<KeccakF1600>::new();
+        data2.permute();
+        assert_ne!(data0.0, data1.0);
+        assert_ne!(data1.0, data2.0);
+        assert_ne!(data0.0, data2.0);
+    }
+
+    #[test]
+    fn test_byte_layout() {
+        let mut state = KittenState::<KitTen>::new();
+        state.write(0, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09]);
+        state.xor_byte(199, 0x80);
+        assert_eq!(state.0[0], 0x0807060504030201);
+        assert_eq!(state.0[1], 0x09);
+        assert_eq!(state.0[24], 0x80 << 56);
+    }
+
+    #[test]
+    fn test_spans_match_bytes() {
+        // Compare each operation against the obvious byte by byte version,
+        // at every alignment, and with lengths crossing multiple words.
+        let initial: [u8; STATE_SIZE_U8] = core::array::from_fn(|i| (i * 7 + 3) as u8);
+        let mut base = KittenState::<KitTen>::new();
+        base.write(0, &initial);
+        assert_eq!(base.to_bytes(), initial);
+        for pos in 0..24 {
+            for len in 0..40 {
+                let data: Vec<u8> = (0..len).map(|i| (i * 13 + 100) as u8).collect();
+                let span = pos..pos + len;
+
+                let mut state = base.clone();
+                state.xor_in(pos, &data);
+                let mut expected = initial;
+                expected[span.clone()]
+                    .iter_mut()
+                    .zip(&data)
+                    .for_each(|(s, d)| *s ^= d);
+                assert_eq!(state.to_bytes(), expected);
+
+                let mut state = base.clone();
+                let mut out = data.clone();
+                state.xor_in_out(pos, &mut out);
+                assert_eq!(state.to_bytes(), expected);
+                assert_eq!(out, &expected[span.clone()]);
+
+                let mut state = base.clone();
+                let mut out = data.clone();
+                state.exchange(pos, &mut out);
+                let mut expected = initial;
+                expected[span.clone()].copy_from_slice(&data);
+                assert_eq!(state.to_bytes(), expected);
+                let xored: Vec<u8> = data
+                    .iter()
+                    .zip(&initial[span.clone()])
+                    .map(|(a, b)| a ^ b)
+                    .collect();
+                assert_eq!(out, xored);
+
+                let mut state = base.clone();
+                state.write(pos, &data);
+                assert_eq!(state.to_bytes(), expected);
+
+                let mut out = vec![0; len];
+                base.read(pos, &mut out);
+                assert_eq!(out, &initial[span.clone()]);
+
+                let mut state = base.clone();
+                let mut out = vec![0; len];
+                state.take(pos, &mut out);
+                assert_eq!(out, &initial[span.clone()]);
+                let mut expected = initial;
+                expected[span.clone()].fill(0);
+                assert_eq!(state.to_bytes(), expected);
+
+                let mut state = base.clone();
+                state.zero(pos, len);
+                assert_eq!(state.to_bytes(), expected);
+            }
+        }
+    }
 }
@@ -12,8 +12,8 @@
 //! I think 24 rounds is excessive, and instead think that the 10 rounds
 //! of KitTen are sufficient.
 //!
-//! Following in the TMC rationale, I also hardcode 128 bits of security
-//! into the protocol.
+//! Following in the TMC rationale, I also default to 128 bits of security.
+//! A 256 bit security level is also available, for those who need it.
 //!
 //! There are also some slight changes to the protocol, such as not allowing
 //! MACs to be created or verified in a streaming fashion, or setting
@@ -120,6 +120,10 @@
 //! creating and verifying a MAC which attests to the integrity of the entire
 //! transcript thus far.
 //!
+//! If you'd rather not put this together yourself, enabling the `aead` feature
+//! provides `MeowAead`, which follows this pattern, and implements the traits
+//! from the `aead` crate.
+//!
 //! ## Hashing
 //!
 //! It's also possible to use Meow as a very simple hash function:
@@ -141,6 +145,60 @@
 //! operations into multiple calls. The second call to the hash function
 //! is equivalent.
 //!
+//! With the `digest` feature, `MeowHash` wraps this up as a hash function
+//! implementing the traits from the `digest` crate, including its
+//! extendable output traits.
+//!
+//! The same feature also provides `MeowMac`, a keyed version implementing
+//! the `Mac` trait, which verifies tags in constant time.
+//!
+//! ### Hashing Many Messages
+//!
+//! If you have several messages to hash, you can hash them all at once,
+//! which can be quite a bit faster than hashing them one by one:
+//!
+//! ```rust
+//! use ck_meow::Meow;
+//!
+//! let mut hashes = [[0u8; 32]; 4];
+//! let [h0, h1, h2, h3] = &mut hashes;
+//! let meow = Meow::new(b"my hash function");
+//! meow.hash_many([b"one", b"two", b"three", b"four"], [h0, h1, h2, h3]);
+//! ```
+//!
+//! Each hash is exactly the same as calling `ad` and then `prf` on a separate
+//! copy of the state.
+//!
+//! ## Key Derivation
+//!
+//! To derive many keys from a single secret, `MeowKdf` provides an extract
+//! and expand interface, like HKDF:
+//!
+//! ```rust
+//! use ck_meow::MeowKdf;
+//!
+//! let kdf = MeowKdf::extract(b"some salt", b"master secret");
+//! let encryption_key = kdf.expand::<32>(&[b"my app", b"encryption"]);
+//! let mac_key = kdf.expand::<32>(&[b"my app", b"mac"]);
+//! ```
+//!
+//! Every input is framed with its length, so different lists of labels
+//! always produce unrelated keys.
+//!
+//! ## Password Hashing
+//!
+//! With the `password` feature, `MeowBalloon` provides a memory-hard password
+//! hash, following the design of Balloon hashing. Hashes are encoded as PHC
+//! strings, and come with helpers to verify passwords, and to check whether
+//! a hash should be upgraded to new parameters.
+//!
+//! ## Random Number Generation
+//!
+//! With the `rand_core` feature, `MeowRng` is a random number generator
+//! implementing the traits from the `rand_core` crate. It ratchets its state
+//! after every request, so compromising it doesn't reveal earlier outputs.
+//! Enabling the `getrandom` feature allows seeding it from the operating system.
+//!
 //! ## Fiat-Shamirization
 //!
 //! Not only can Meow be used for hashing in one stroke, it's also possible
@@ -162,11 +220,210 @@
 //!
 //! (Note that it would be a good ad to add some `meta_ad` calls for framing,
 //! defining the length of the inputs).
-#![cfg_attr(not(test), no_std)]
+//!
+//! `Transcript` takes care of this framing, following the API of Merlin,
+//! with every message and challenge labelled, and framed with its length.
+//!
+//! Challenges often need to be uniform modulo some group order, rather than
+//! raw bytes. `prf_below_le` and `prf_below_be` squeeze out an integer below
+//! a modulus, using rejection sampling to avoid any bias, and `prf_range` and
+//! `prf_bits` squeeze out integers in a range, and random bits.
+//!
+//! ## Signatures
+//!
+//! With the `signatures` feature, `SigningKey` and `VerifyingKey` provide
+//! Schnorr signatures over the Ristretto255 group, using Meow for the challenges,
+//! and for deriving nonces. Each signature is bound to a context string,
+//! so that signatures for different applications can't be confused.
+//!
+//! ## Handshakes
+//!
+//! With the `handshake` feature, `Handshake` implements the NN, NK, KK, XX,
+//! and IK patterns from the Noise protocol framework, over X25519. Like Disco,
+//! the entire handshake runs on a single Meow instance: the shared secrets
+//! are absorbed with `key`, static keys are encrypted with `send_enc`,
+//! and each message is authenticated with `send_mac`. The result is a Meow
+//! instance ready to exchange messages, along with a hash of the handshake.
+//!
+//! ## Sessions
+//!
+//! Once both parties share a keyed Meow instance, `Session` exchanges encrypted
+//! records over it. Each record carries the length of its message, and a MAC,
+//! and is bound to an implicit sequence number, so records can't be dropped,
+//! replayed, or reordered. The state is ratcheted regularly, and a record
+//! failing to authenticate poisons the session for good.
+//!
+//! Sessions are half-duplex, with both parties taking turns. `Meow::split`
+//! derives an independent state for each direction instead, giving `Sender`
+//! and `Receiver` halves which can be used from different threads.
+//!
+//! ## Streaming Encryption
+//!
+//! Using `more`, a single `send_enc` can encrypt a large amount of data, but the
+//! MAC only comes at the very end, so the receiver can't safely use any of the
+//! data before that. With the `std` feature, `EncryptWriter` and `DecryptReader`
+//! split the data into chunks, each with its own MAC, and bound to its position,
+//! and to whether or not it ends the stream. The reader only ever returns
+//! authenticated data, and detects chunks being reordered, or the stream being truncated.
+//!
+//! ## Async IO
+//!
+//! With the `tokio` feature, `AsyncEncryptWriter` and `AsyncDecryptReader` wrap
+//! async streams, sealing data into records with their own MACs. `Session`,
+//! and the `Sender` and `Receiver` halves of a split state, also implement the
+//! `Encoder` and `Decoder` traits from `tokio_util`, for sending length-delimited
+//! encrypted frames.
+//!
+//! ## Seekable Files
+//!
+//! Because Meow is a sequential duplex, reading the end of a long ciphertext
+//! normally means processing everything before it. `SeekableFile` instead
+//! derives an independent state for each chunk of a file, bound to the chunk's
+//! index and the file's identifier, so that any chunk can be decrypted and
+//! authenticated on its own. An authenticated header records the chunk size
+//! and total length. With the `std` feature, `SeekableWriter` and `SeekableReader`
+//! provide random access to such files through the `Write`, `Read`, and `Seek` traits.
+//!
+//! ## Choosing a Security Level
+//!
+//! By default, Meow provides 128 bits of security, but you can also ask
+//! for 256 bits, at the cost of absorbing data more slowly:
+//!
+//! ```rust
+//! use ck_meow::Meow;
+//!
+//! let mut meow = Meow::<256>::init(b"my protocol");
+//! meow.ad(b"some data", false);
+//! let mut hash = [0u8; 64];
+//! meow.prf(&mut hash, false);
+//! ```
+//!
+//! The security level is absorbed into the initial state, so instances
+//! with different levels will never interoperate.
+//!
+//! These are the only two levels supported: something like `Meow<192>`
+//! will be rejected by the compiler.
+//!
+//! ## Choosing a Permutation
+//!
+//! By default, Meow uses KitTen, but if you'd like a larger security margin,
+//! you can also pick a permutation with more rounds. The permutation comes
+//! after the security level, so both need to be written out:
+//!
+//! ```rust
+//! use ck_meow::{KeccakF1600, Meow};
+//!
+//! let mut meow = Meow::<128, KeccakF1600>::init(b"my protocol");
+//! meow.ad(b"some data", false);
+//! let mut hash = [0u8; 32];
+//! meow.prf(&mut hash, false);
+//! ```
+//!
+//! The choice of permutation is absorbed into the initial state, so instances
+//! using different permutations will never interoperate.
+//!
+//! ## Debugging
+//!
+//! When two parties get out of sync, the only symptom is usually a MAC
+//! failing to verify. Enabling the `trace` feature makes each instance record
+//! every operation it does, which you can get with `Meow::trace`. Comparing
+//! the traces of both parties with `Trace::first_divergence` will then tell
+//! you which operation they disagreed on.
+//!
+//! ## STROBE Compatibility
+//!
+//! If you need to interoperate with an existing implementation of STROBE,
+//! you can use the [`Strobe`] type instead. This matches version 1.0.2 of
+//! the reference implementation exactly, using the full Keccak-f\[1600\]
+//! permutation, and allowing MACs to be streamed.
+//!
+//! ```rust
+//! use ck_meow::Strobe;
+//!
+//! let mut strobe = Strobe::new(b"my protocol");
+//! strobe.ad(b"some data", false);
+//! let mut mac = [0u8; 16];
+//! strobe.send_mac(&mut mac[..8], false);
+//! strobe.send_mac(&mut mac[8..], true);
+//! ```
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![forbid(unsafe_code)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "aead")]
+mod aead;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod batch;
+mod channel;
+mod export;
+mod framing;
+#[cfg(feature = "handshake")]
+mod handshake;
+#[cfg(feature = "digest")]
+mod hash;
+mod kdf;
 mod kitten;
+#[cfg(feature = "digest")]
+mod mac;
 mod meow;
+#[cfg(feature = "password")]
+mod password;
+mod permutation;
+#[cfg(feature = "rand_core")]
+mod rng;
+mod sample;
+mod seekable;
+mod session;
+#[cfg(feature = "signatures")]
+mod signature;
+mod strobe;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "trace")]
+mod trace;
+mod transcript;
 // For much heavier tests.
 #[cfg(test)]
 mod test;
 
-pub use crate::meow::{MacError, Meow};
+#[cfg(feature = "aead")]
+pub use crate::aead::MeowAead;
+#[cfg(feature = "tokio")]
+pub use crate::async_io::{AsyncDecryptReader, AsyncEncryptWriter};
+pub use crate::channel::{Receiver, Sender};
+pub use crate::export::{ImportError, ENCRYPTED_EXPORT_LEN, EXPORT_LEN};
+#[cfg(feature = "handshake")]
+pub use crate::handshake::{
+    Handshake, HandshakeError, HandshakeOutput, Pattern, HANDSHAKE_HASH_LEN, HANDSHAKE_MAC_LEN,
+};
+#[cfg(feature = "digest")]
+pub use crate::hash::{MeowHash, MeowXofReader};
+pub use crate::kdf::MeowKdf;
+#[cfg(feature = "digest")]
+pub use crate::mac::MeowMac;
+pub use crate::meow::{MacError, Meow, MeowError, Role, Security, SupportedSecurity};
+#[cfg(feature = "password")]
+pub use crate::password::{MeowBalloon, PasswordError};
+pub use crate::permutation::{KeccakF1600, KitTen, Permutation, TurboShake};
+#[cfg(feature = "rand_core")]
+pub use crate::rng::MeowRng;
+pub use crate::seekable::{
+    SeekableError, SeekableFile, FILE_ID_LEN, SEEKABLE_HEADER_LEN, SEEKABLE_MAC_LEN,
+};
+#[cfg(feature = "std")]
+pub use crate::seekable::{SeekableReader, SeekableWriter};
+pub use crate::session::{
+    Session, SessionError, RECORD_HEADER_LEN, RECORD_MAC_LEN, RECORD_OVERHEAD,
+};
+#[cfg(feature = "signatures")]
+pub use crate::signature::{Signature, SignatureError, SigningKey, VerifyingKey, SIGNATURE_LEN};
+pub use crate::strobe::Strobe;
+#[cfg(feature = "std")]
+pub use crate::stream::{DecryptReader, EncryptWriter, DEFAULT_CHUNK_SIZE, STREAM_MAC_LEN};
+#[cfg(feature = "trace")]
+pub use crate::trace::{Divergence, Trace, TraceEntry};
+pub use crate::transcript::Transcript;
+#[cfg(feature = "rand_core")]
+pub use crate::transcript::{TranscriptRng, TranscriptRngBuilder};
@@ -0,0 +1,150 @@
+use digest::{
+    consts::U32, crypto_common::KeySizeUser, FixedOutput, FixedOutputReset, InvalidLength, Key,
+    KeyInit, MacMarker, Output, OutputSizeUser, Reset, Update,
+};
+
+use crate::meow::Meow;
+
+/// The protocol string used by [`MeowMac`].
+const MAC_PROTOCOL: &[u8] = b"ck-meow MAC";
+
+/// A message authentication code built on top of Meow.
+///
+/// This implements the traits from the `digest` crate, including `Mac`,
+/// producing 32 byte tags. Keys are normally 32 bytes, but keys of any length
+/// can be used with `new_from_slice`.
+///
+/// Computing a tag is the same as including the key with `key`, absorbing
+/// the message with `ad`, and squeezing out the tag with `prf`.
+/// Verification compares tags in constant time, without needing a
+/// mutable buffer:
+///
+/// ```
+/// use ck_meow::MeowMac;
+/// use digest::Mac;
+///
+/// let mut mac = MeowMac::new_from_slice(b"my secret key").unwrap();
+/// mac.update(b"a message");
+/// let tag = mac.finalize().into_bytes();
+///
+/// let mut mac = MeowMac::new_from_slice(b"my secret key").unwrap();
+/// mac.update(b"a message");
+/// assert!(mac.verify_slice(&tag).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct MeowMac {
+    /// The state, having started absorbing the message.
+    meow: Meow,
+    /// The state, after including the key, used to reset.
+    initial: Meow,
+}
+
+impl MeowMac {
+    fn with_key(key: &[u8]) -> Self {
+        let mut initial = Meow::new(MAC_PROTOCOL);
+        initial.key(key, false);
+        let mut meow = initial.clone();
+        // We start an empty operation, so that all updates can continue it.
+        meow.ad(&[], false);
+        Self { meow, initial }
+    }
+}
+
+impl KeySizeUser for MeowMac {
+    type KeySize = U32;
+}
+
+impl KeyInit for MeowMac {
+    fn new(key: &Key<Self>) -> Self {
+        Self::with_key(key)
+    }
+
+    fn new_from_slice(key: &[u8]) -> Result<Self, InvalidLength> {
+        Ok(Self::with_key(key))
+    }
+}
+
+impl MacMarker for MeowMac {}
+
+impl OutputSizeUser for MeowMac {
+    type OutputSize = U32;
+}
+
+impl Update for MeowMac {
+    fn update(&mut self, data: &[u8]) {
+        self.meow.ad(data, true);
+    }
+}
+
+impl FixedOutput for MeowMac {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.meow.prf(out, false);
+    }
+}
+
+impl FixedOutputReset for MeowMac {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.meow.prf(out, false);
+        self.reset();
+    }
+}
+
+impl Reset for MeowMac {
+    fn reset(&mut self) {
+        self.meow = self.initial.clone();
+        self.meow.ad(&[], false);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use digest::Mac;
+
+    fn tag(key: &[u8], data: &[u8]) -> [u8; 32] {
+        let mut mac = <MeowMac as Mac>::new_from_slice(key).unwrap();
+        Mac::update(&mut mac, data);
+        mac.finalize().into_bytes().into()
+    }
+
+    #[test]
+    fn test_matches_meow() {
+        let mut meow = Meow::new(MAC_PROTOCOL);
+        meow.key(b"key", false);
+        meow.ad(b"message", false);
+        let mut expected = [0u8; 32];
+        meow.prf(&mut expected, false);
+        assert_eq!(tag(b"key", b"message"), expected);
+
+        let mut mac = <MeowMac as KeyInit>::new(&[7u8; 32].into());
+        Mac::update(&mut mac, b"mess");
+        Mac::update(&mut mac, b"age");
+        assert_eq!(mac.finalize().into_bytes()[..], tag(&[7u8; 32], b"message"));
+    }
+
+    #[test]
+    fn test_verify() {
+        let good = tag(b"key", b"message");
+        let verify = |key: &[u8], data: &[u8], tag: &[u8]| {
+            let mut mac = <MeowMac as Mac>::new_from_slice(key).unwrap();
+            Mac::update(&mut mac, data);
+            mac.verify_slice(tag).is_ok()
+        };
+        assert!(verify(b"key", b"message", &good));
+        assert!(!verify(b"other key", b"message", &good));
+        assert!(!verify(b"key", b"other message", &good));
+        let mut bad = good;
+        bad[31] ^= 1;
+        assert!(!verify(b"key", b"message", &bad));
+        assert!(!verify(b"key", b"message", &good[..16]));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut mac = <MeowMac as Mac>::new_from_slice(b"key").unwrap();
+        Mac::update(&mut mac, b"message");
+        let first = mac.finalize_reset().into_bytes();
+        Mac::update(&mut mac, b"message");
+        assert_eq!(mac.finalize().into_bytes(), first);
+    }
+}
@@ -1,32 +1,75 @@
 use core::fmt;
 
 // See: https://strobe.sourceforge.io/specs for the specification for STROBE.
-use crate::kitten::{AlignedKittenState, STATE_SIZE_U8};
+#[cfg(feature = "trace")]
+use crate::trace::Trace;
+use crate::{
+    kitten::{KittenState, STATE_SIZE_U8},
+    permutation::{KitTen, Permutation},
+};
 use subtle::{Choice, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
-/// We use a hard coded security parameter of 128 bits.
-const SECURITY_PARAM: usize = 128;
-/// This is the rate of our sponge, given our security parameter.
-const MEOW_R: u8 = (STATE_SIZE_U8 - (2 * SECURITY_PARAM) / 8 - 2) as u8;
+/// A marker for a security level, in bits.
+///
+/// This is used to restrict the security levels `Meow` accepts, through the
+/// [`SupportedSecurity`] trait.
+#[derive(Clone, Copy, Debug)]
+pub struct Security<const BITS: usize>;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Security<128> {}
+    impl Sealed for super::Security<256> {}
+}
+
+/// Implemented for the security levels Meow supports: 128 and 256 bits.
+///
+/// This trait is sealed, so no other levels can be used. This means that a type
+/// like `Meow<129>` is rejected by the compiler:
+///
+/// ```compile_fail
+/// let meow = ck_meow::Meow::<129>::init(b"my protocol");
+/// ```
+pub trait SupportedSecurity: sealed::Sealed {}
+
+impl SupportedSecurity for Security<128> {}
+impl SupportedSecurity for Security<256> {}
+
+/// Calculate the rate of our sponge, given a security parameter, in bits.
+///
+/// Only security levels of 128 or 256 bits are allowed.
+const fn rate(security: usize) -> u8 {
+    assert!(
+        security == 128 || security == 256,
+        "The security level must be either 128 or 256 bits."
+    );
+    (STATE_SIZE_U8 - (2 * security) / 8 - 2) as u8
+}
 /// The context string we use when initializing our construction.
-const MEOW_CONTEXT: &[u8] = b"Meow v0.1.0";
+const MEOW_CONTEXT: &[u8] = b"Meow v0.2.0";
+/// The maximum length of the name of a permutation.
+///
+/// The name follows the header, the context string, and a length byte in the
+/// initial block, which needs to fit into the rate, even at the highest security level.
+const MAX_PERMUTATION_NAME_LEN: usize = rate(256) as usize + 2 - 6 - MEOW_CONTEXT.len() - 1;
 
 // 6.2: Operations and flags.
-type Flags = u8;
+pub(crate) type Flags = u8;
 
 // Inbound flag. This is set when receiving data.
-const FLAG_I: Flags = 0b00000001;
+pub(crate) const FLAG_I: Flags = 0b00000001;
 // Application flag. If set, data is moving to or from the application.
-const FLAG_A: Flags = 0b00000010;
+pub(crate) const FLAG_A: Flags = 0b00000010;
 // Cipher flag. If set, the output depends on the cipher state.
-const FLAG_C: Flags = 0b00000100;
+pub(crate) const FLAG_C: Flags = 0b00000100;
 // Transport flag. If set, the operation sends or receives dat on the transport.
-const FLAG_T: Flags = 0b00001000;
+pub(crate) const FLAG_T: Flags = 0b00001000;
 // Meta flag. If set, indicates that the operation is handling metadata.
-const FLAG_M: Flags = 0b00010000;
-// Keytree flag. It's a mystery.
-const FLAG_K: Flags = 0b00100000;
+pub(crate) const FLAG_M: Flags = 0b00010000;
+// Keytree flag. If set, indicates that key material is absorbed a single bit at a time.
+pub(crate) const FLAG_K: Flags = 0b00100000;
 
 /// Represents the role of a participant.
 ///
@@ -34,14 +77,14 @@ const FLAG_K: Flags = 0b00100000;
 /// to the same result. Each of them modifies their role to be either the initiator
 /// or the responder, and this allows their state to be synchronized, since
 /// both parties agree on their respective roles.
-#[derive(Clone, Copy, Debug, PartialEq, Zeroize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Zeroize)]
 #[repr(u8)]
-enum Role {
+pub enum Role {
     /// We don't know which role we play yet.
     Undecided = 2,
-    // We're the first person to send a message.
+    /// We're the first person to send a message.
     Initiator = 0,
-    // We're the first person to receive a message.
+    /// We're the first person to receive a message.
     Responder = 1,
 }
 
@@ -76,7 +119,49 @@ impl fmt::Display for MacError {
     }
 }
 
-fn check_zero(data: &[u8]) -> Result<(), MacError> {
+impl core::error::Error for MacError {}
+
+/// An error produced by the fallible variants of each operation.
+///
+/// The regular operations panic in the situations where these variants
+/// return an error, except for MAC failures, which produce a [`MacError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MeowError {
+    /// An operation using `more` didn't continue the previous operation.
+    ///
+    /// This contains the flags of the previous operation, and those of the
+    /// operation which tried to continue it.
+    Continuation {
+        /// The flags of the previous operation.
+        previous: u8,
+        /// The flags of the operation continuing it.
+        current: u8,
+    },
+    /// A MAC failed to verify.
+    Mac,
+}
+
+impl fmt::Display for MeowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeowError::Continuation { previous, current } => {
+                write!(f, "Cannot continue {:#b} with {:#b}.", previous, current)
+            }
+            MeowError::Mac => MacError.fmt(f),
+        }
+    }
+}
+
+impl From<MacError> for MeowError {
+    fn from(_: MacError) -> Self {
+        MeowError::Mac
+    }
+}
+
+impl core::error::Error for MeowError {}
+
+pub(crate) fn check_zero(data: &[u8]) -> Result<(), MacError> {
     let mut ok = Choice::from(1);
     for b in data {
         ok &= b.ct_eq(&0u8);
@@ -108,6 +193,18 @@ fn check_zero(data: &[u8]) -> Result<(), MacError> {
 /// For example, the PRF output from both states will be the same right
 /// after forking them.
 ///
+/// The state is generic over the security level it provides, in bits,
+/// which is either 128 or 256, defaulting to 128. No other levels are valid,
+/// see [`SupportedSecurity`]. A higher security level makes the state absorb
+/// data more slowly. For example, `Meow<256>` provides 256 bits of security.
+///
+/// The state is also generic over the permutation it uses, which defaults to
+/// [`KitTen`]. Because the security level comes first, picking a permutation
+/// also means spelling out the security level, e.g. `Meow<128, KeccakF1600>`.
+///
+/// Instances using different security levels or permutations are domain separated,
+/// and will never agree with each other.
+///
 /// Many operations are divided into `send` and `recv` pairs. The idea is that
 /// one party performs `send`, sends some data, and then the other party uses
 /// `recv` with this data.
@@ -125,12 +222,18 @@ fn check_zero(data: &[u8]) -> Result<(), MacError> {
 /// of the previous call.
 #[cfg_attr(test, derive(Debug))]
 #[derive(Clone, ZeroizeOnDrop)]
-pub struct Meow {
-    state: AlignedKittenState,
-    pos: u8,
-    pos_begin: u8,
-    role: Role,
-    cur_flags: Flags,
+pub struct Meow<const SECURITY: usize = 128, P: Permutation = KitTen>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    pub(crate) state: KittenState<P>,
+    pub(crate) pos: u8,
+    pub(crate) pos_begin: u8,
+    pub(crate) role: Role,
+    pub(crate) cur_flags: Flags,
+    #[cfg(feature = "trace")]
+    #[zeroize(skip)]
+    pub(crate) trace: Trace,
 }
 
 impl Meow {
@@ -139,16 +242,58 @@ impl Meow {
     /// This function takes in a protocol string, which gets hashed into the state.
     /// The intention is to use this for domain separation of different protocols based on Meow.
     pub fn new(protocol: &[u8]) -> Self {
-        let mut state = AlignedKittenState([0u8; STATE_SIZE_U8]);
+        Self::init(protocol)
+    }
+}
+
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// The rate of our sponge, given our security level.
+    pub(crate) const R: u8 = rate(SECURITY);
+    /// The length of the name of our permutation, which we check to be small enough.
+    const PERMUTATION_NAME_LEN: u8 = {
+        assert!(
+            P::NAME.len() <= MAX_PERMUTATION_NAME_LEN,
+            "Permutation name is too long."
+        );
+        P::NAME.len() as u8
+    };
+
+    /// Create a new Meow instance, with explicitly chosen parameters.
+    ///
+    /// This is like `new`, except that you can pick a security level and permutation
+    /// other than the default, e.g. `Meow::<256>::init(b"my protocol")`,
+    /// or `Meow::<128, KeccakF1600>::init(b"my protocol")`.
+    pub fn init(protocol: &[u8]) -> Self {
+        // We follow our context string with the length prefixed name of our permutation.
+        Self::with_context(
+            &[MEOW_CONTEXT, &[Self::PERMUTATION_NAME_LEN], P::NAME],
+            protocol,
+        )
+    }
+
+    /// Create a new instance, given the pieces of the context string to use.
+    ///
+    /// This context string is placed in the initial block of the state.
+    pub(crate) fn with_context(context: &[&[u8]], protocol: &[u8]) -> Self {
+        let mut state = KittenState::new();
         // "5.1:
         // The initial state of the object is as follows:
         // st = F([0x01, R+2, 0x01, 0x00, 0x01, 0x60] + ascii("STROBEvX.Y.Z"))
         // pos = posbegin = 0
         // I0 = None"
         //
-        // Instead, we use a different context string.
-        state[0..6].copy_from_slice(&[0x01, (MEOW_R as u8) + 2, 0x01, 0x00, 0x01, 0x60]);
-        state[6..6 + MEOW_CONTEXT.len()].copy_from_slice(MEOW_CONTEXT);
+        // Instead of "STROBEvX.Y.Z", we let the caller choose the context string.
+        // Note that the rate depends on the security level, so including it
+        // here separates the different levels.
+        state.write(0, &[0x01, Self::R + 2, 0x01, 0x00, 0x01, 0x60]);
+        let mut i = 6;
+        for piece in context {
+            state.write(i, piece);
+            i += piece.len();
+        }
         state.permute();
 
         let mut out = Self {
@@ -157,6 +302,8 @@ impl Meow {
             pos_begin: 0,
             role: Role::Undecided,
             cur_flags: 0,
+            #[cfg(feature = "trace")]
+            trace: Trace::default(),
         };
 
         out.meta_ad(protocol, false);
@@ -172,8 +319,7 @@ impl Meow {
     /// The semantics of this are also that each party already knows the data,
     /// and doesn't have to send it to the other person.
     pub fn ad(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_A, more);
-        self.absorb(data);
+        self.try_ad(data, more).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Absorb additional metadata into this state.
@@ -181,8 +327,8 @@ impl Meow {
     /// This is intended to be used to describe additional data, or for
     /// framing: describing the operations being done.
     pub fn meta_ad(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_M | FLAG_A, more);
-        self.absorb(data);
+        self.try_meta_ad(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Include a secret key into the state.
@@ -191,8 +337,25 @@ impl Meow {
     ///
     /// For forward secrecy, the state is also ratcheted.
     pub fn key(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_A | FLAG_C, more);
-        self.overwrite(data);
+        self.try_key(data, more).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Include a secret key into the state, one bit at a time.
+    ///
+    /// This is like `key`, except that the permutation is run after absorbing
+    /// each bit of the key. Each call to the permutation thus only mixes in
+    /// a single unknown bit, which makes it much harder to recover the key
+    /// through side channels like power analysis, which rely on observing
+    /// many different inputs to the same computation involving the key.
+    ///
+    /// This is a lot slower than `key`, running the permutation 8 times
+    /// for each byte of the key, so it's meant for loading long term keys
+    /// on devices exposed to such attacks.
+    ///
+    /// This produces a different state than using `key` with the same data.
+    pub fn key_tree(&mut self, data: &[u8], more: bool) {
+        self.try_key_tree(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send some plaintext data to the other party.
@@ -201,8 +364,8 @@ impl Meow {
     /// will not already know this information, and so we additionally have
     /// to send it to them.
     pub fn send_clr(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_A | FLAG_T, more);
-        self.absorb(data);
+        self.try_send_clr(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send some plaintext metadata to the other party.
@@ -210,24 +373,24 @@ impl Meow {
     /// Similarly to `send_clr`, the semantics are that the other party doesn't
     /// know this information, and we need to send it to them.
     pub fn meta_send_clr(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_M | FLAG_A | FLAG_T, more);
-        self.absorb(data);
+        self.try_meta_send_clr(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Receive plaintext data.
     ///
     /// This is the counterpart to `send_clr`.
     pub fn recv_clr(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_I | FLAG_A | FLAG_T, more);
-        self.absorb(data);
+        self.try_recv_clr(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Receive plaintext metadata.
     ///
     /// This is the counterpart to `meta_recv_clr`.
     pub fn meta_recv_clr(&mut self, data: &[u8], more: bool) {
-        self.begin_op(FLAG_M | FLAG_I | FLAG_A | FLAG_T, more);
-        self.absorb(data);
+        self.try_meta_recv_clr(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send encrypted data.
@@ -236,8 +399,8 @@ impl Meow {
     /// it in place to contain the encrypted data. This should then be sent
     /// to the other party.
     pub fn send_enc(&mut self, data: &mut [u8], more: bool) {
-        self.begin_op(FLAG_A | FLAG_C | FLAG_T, more);
-        self.absorb_and_set(data);
+        self.try_send_enc(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send encrypted metadata.
@@ -245,8 +408,8 @@ impl Meow {
     /// The intention of this operation is to send encrypted framing data,
     /// which might be useful for some situations.
     pub fn meta_send_enc(&mut self, data: &mut [u8], more: bool) {
-        self.begin_op(FLAG_M | FLAG_A | FLAG_C | FLAG_T, more);
-        self.absorb_and_set(data);
+        self.try_meta_send_enc(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Receive encrypted data.
@@ -256,14 +419,14 @@ impl Meow {
     /// We start with a buffer of encrypted data, and then modify it to contain
     /// the plaintext.
     pub fn recv_enc(&mut self, data: &mut [u8], more: bool) {
-        self.begin_op(FLAG_I | FLAG_A | FLAG_C | FLAG_T, more);
-        self.exchange(data);
+        self.try_recv_enc(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Received encrypted metadata.
     pub fn meta_recv_enc(&mut self, data: &mut [u8], more: bool) {
-        self.begin_op(FLAG_M | FLAG_I | FLAG_A | FLAG_C | FLAG_T, more);
-        self.exchange(data);
+        self.try_meta_recv_enc(data, more)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send a MAC to the other party.
@@ -275,16 +438,15 @@ impl Meow {
     /// This operation intentionally does not allow `more` to be used. This
     /// is to match `recv_mac`.
     pub fn send_mac(&mut self, data: &mut [u8]) {
-        self.begin_op(FLAG_C | FLAG_T, false);
-        self.copy(data);
+        self.try_send_mac(data).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Send a MAC of metadata to the other party.
     ///
     /// This is very similar to `send_mac`.
     pub fn meta_send_mac(&mut self, data: &mut [u8]) {
-        self.begin_op(FLAG_M | FLAG_C | FLAG_T, false);
-        self.copy(data);
+        self.try_meta_send_mac(data)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Receive and verify a MAC.
@@ -295,24 +457,38 @@ impl Meow {
     /// This operation intentionally does not allow `more` to be used. This
     /// is because a MAC should always be verified all at once, rather than in chunks.
     pub fn recv_mac(&mut self, data: &mut [u8]) -> Result<(), MacError> {
-        self.begin_op(FLAG_I | FLAG_C | FLAG_T, false);
-        self.exchange(data);
-        check_zero(data)
+        self.try_recv_mac(data).map_err(|e| match e {
+            MeowError::Mac => MacError,
+            e => panic!("{}", e),
+        })
+    }
+
+    /// Decrypt a message with `recv_enc`, and then verify its MAC with `recv_mac`.
+    ///
+    /// If the MAC fails, the decrypted message gets zeroed, so that we never
+    /// release unauthenticated plaintext.
+    pub(crate) fn recv_enc_and_mac(
+        &mut self,
+        data: &mut [u8],
+        mac: &mut [u8],
+    ) -> Result<(), MacError> {
+        self.recv_enc(data, false);
+        self.recv_mac(mac).inspect_err(|_| data.zeroize())
     }
 
     /// Receive and verify a MAC of metadata.
     ///
     /// This is very similar to `recv_mac`.
     pub fn meta_recv_mac(&mut self, data: &mut [u8]) -> Result<(), MacError> {
-        self.begin_op(FLAG_M | FLAG_I | FLAG_C | FLAG_T, false);
-        self.exchange(data);
-        check_zero(data)
+        self.try_meta_recv_mac(data).map_err(|e| match e {
+            MeowError::Mac => MacError,
+            e => panic!("{}", e),
+        })
     }
 
     /// Generate random bytes from the state.
     pub fn prf(&mut self, data: &mut [u8], more: bool) {
-        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
-        self.squeeze(data);
+        self.try_prf(data, more).unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Ratchet the state forward.
@@ -321,7 +497,7 @@ impl Meow {
     /// to derive information about old states. Ratcheting prevents this flow
     /// of information backwards.
     pub fn ratchet(&mut self) {
-        self.ratchet_many(SECURITY_PARAM / 8, false)
+        self.try_ratchet().unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Ratchet the state forward many times.
@@ -333,66 +509,316 @@ impl Meow {
     ///
     /// That said, you probably want a function dedicated for hashing passwords,
     /// which have other security features, like being memory hard, and things like
-    /// that.
+    /// that. The `password` feature provides one, as `MeowBalloon`.
     pub fn ratchet_many(&mut self, len: usize, more: bool) {
-        self.begin_op(FLAG_C, more);
+        self.try_ratchet_many(len, more)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+// These variants of each operation return an error when the operation is misused,
+// instead of panicking, which is useful when the operations depend on untrusted input.
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// Like [`Self::ad`], but returning an error instead of panicking on misuse.
+    pub fn try_ad(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_A, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_ad`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_ad(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_A, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::key`], but returning an error instead of panicking on misuse.
+    pub fn try_key(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_A | FLAG_C, more, data.len())?;
+        self.overwrite(data);
+        Ok(())
+    }
+
+    /// Like [`Self::key_tree`], but returning an error instead of panicking on misuse.
+    pub fn try_key_tree(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_A | FLAG_C | FLAG_K, more, data.len())?;
+        for byte in data {
+            for i in 0..8 {
+                self.overwrite(&[(byte >> i) & 1]);
+                self.run_f();
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::send_clr`], but returning an error instead of panicking on misuse.
+    pub fn try_send_clr(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_A | FLAG_T, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_send_clr`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_send_clr(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_A | FLAG_T, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::recv_clr`], but returning an error instead of panicking on misuse.
+    pub fn try_recv_clr(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_I | FLAG_A | FLAG_T, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_recv_clr`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_recv_clr(&mut self, data: &[u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_I | FLAG_A | FLAG_T, more, data.len())?;
+        self.absorb(data);
+        Ok(())
+    }
+
+    /// Like [`Self::send_enc`], but returning an error instead of panicking on misuse.
+    pub fn try_send_enc(&mut self, data: &mut [u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_A | FLAG_C | FLAG_T, more, data.len())?;
+        self.absorb_and_set(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_send_enc`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_send_enc(&mut self, data: &mut [u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_A | FLAG_C | FLAG_T, more, data.len())?;
+        self.absorb_and_set(data);
+        Ok(())
+    }
+
+    /// Like [`Self::recv_enc`], but returning an error instead of panicking on misuse.
+    pub fn try_recv_enc(&mut self, data: &mut [u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_I | FLAG_A | FLAG_C | FLAG_T, more, data.len())?;
+        self.exchange(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_recv_enc`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_recv_enc(&mut self, data: &mut [u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_I | FLAG_A | FLAG_C | FLAG_T, more, data.len())?;
+        self.exchange(data);
+        Ok(())
+    }
+
+    /// Like [`Self::send_mac`], but returning an error instead of panicking on misuse.
+    pub fn try_send_mac(&mut self, data: &mut [u8]) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_C | FLAG_T, false, data.len())?;
+        self.copy(data);
+        Ok(())
+    }
+
+    /// Like [`Self::meta_send_mac`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_send_mac(&mut self, data: &mut [u8]) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_C | FLAG_T, false, data.len())?;
+        self.copy(data);
+        Ok(())
+    }
+
+    /// Like [`Self::recv_mac`], but returning an error instead of panicking on misuse.
+    pub fn try_recv_mac(&mut self, data: &mut [u8]) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_I | FLAG_C | FLAG_T, false, data.len())?;
+        self.exchange(data);
+        check_zero(data)?;
+        Ok(())
+    }
+
+    /// Like [`Self::meta_recv_mac`], but returning an error instead of panicking on misuse.
+    pub fn try_meta_recv_mac(&mut self, data: &mut [u8]) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_M | FLAG_I | FLAG_C | FLAG_T, false, data.len())?;
+        self.exchange(data);
+        check_zero(data)?;
+        Ok(())
+    }
+
+    /// Like [`Self::prf`], but returning an error instead of panicking on misuse.
+    pub fn try_prf(&mut self, data: &mut [u8], more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_I | FLAG_A | FLAG_C, more, data.len())?;
+        self.squeeze(data);
+        Ok(())
+    }
+
+    /// Like [`Self::ratchet`], but returning an error instead of panicking on misuse.
+    pub fn try_ratchet(&mut self) -> Result<(), MeowError> {
+        self.try_ratchet_many(SECURITY / 8, false)
+    }
+
+    /// Like [`Self::ratchet_many`], but returning an error instead of panicking on misuse.
+    pub fn try_ratchet_many(&mut self, len: usize, more: bool) -> Result<(), MeowError> {
+        self.try_begin_op(FLAG_C, more, len)?;
         self.zero_out(len);
+        Ok(())
     }
 }
 
-impl Meow {
+#[cfg(feature = "trace")]
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// The trace of the operations done by this instance so far.
+    ///
+    /// This includes the operation absorbing the protocol string.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Take the trace of the operations done so far, starting a new one.
+    pub fn take_trace(&mut self) -> Trace {
+        core::mem::take(&mut self.trace)
+    }
+}
+
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
     /// See: 7.1, running F
     fn run_f(&mut self) {
-        self.state[self.pos as usize] &= self.pos_begin;
-        self.state[self.pos as usize + 1] &= 0x04;
-        self.state[MEOW_R as usize + 1] ^= 0x80;
+        self.pad_f();
         self.state.permute();
+        self.finish_f();
+    }
+
+    /// The part of running F which happens before applying the permutation.
+    pub(crate) fn pad_f(&mut self) {
+        self.state.xor_byte(self.pos as usize, self.pos_begin);
+        self.state.xor_byte(self.pos as usize + 1, 0x04);
+        self.state.xor_byte(Self::R as usize + 1, 0x80);
+    }
+
+    /// The part of running F which happens after applying the permutation.
+    pub(crate) fn finish_f(&mut self) {
         self.pos = 0;
         self.pos_begin = 0;
     }
 
+    /// Access the underlying state, in order to permute it.
+    pub(crate) fn state_mut(&mut self) -> &mut KittenState<P> {
+        &mut self.state
+    }
+
+    /// Check whether the current block is full, and F needs to run.
+    ///
+    /// This only happens when using the operations which don't run F themselves.
+    pub(crate) fn block_full(&self) -> bool {
+        self.pos == Self::R
+    }
+
+    /// Check whether nothing has been absorbed into the current block yet.
+    pub(crate) fn block_empty(&self) -> bool {
+        self.pos == 0
+    }
+
+    /// Absorb as much data as fits into the current block, returning the rest.
+    ///
+    /// Unlike `absorb`, this will not run F once the block is full.
+    pub(crate) fn absorb_in_block<'a>(&mut self, data: &'a [u8]) -> &'a [u8] {
+        let (now, rest) = data.split_at(self.block_len(data.len()));
+        self.state.xor_in(self.pos as usize, now);
+        self.pos += now.len() as u8;
+        rest
+    }
+
+    /// Squeeze as much data as fits into the current block, returning the rest.
+    ///
+    /// Unlike `squeeze`, this will not run F once the block is full.
+    pub(crate) fn squeeze_in_block<'a>(&mut self, data: &'a mut [u8]) -> &'a mut [u8] {
+        let len = self.block_len(data.len());
+        let (now, rest) = data.split_at_mut(len);
+        self.state.take(self.pos as usize, now);
+        self.pos += now.len() as u8;
+        rest
+    }
+
+    /// Look at the raw bytes of the state, for testing.
+    #[cfg(test)]
+    pub(crate) fn state_bytes(&self) -> [u8; STATE_SIZE_U8] {
+        self.state.to_bytes()
+    }
+
     /// Move our writing position forward, possibly running the permutation
     /// if we run out of space.
     #[inline(always)]
-    fn advance_pos(&mut self) {
-        self.pos += 1;
-        if self.pos == MEOW_R {
+    fn advance_pos(&mut self, len: usize) {
+        self.pos += len as u8;
+        if self.pos == Self::R {
             self.run_f();
         }
     }
 
-    /// Absorb some data into this sponge.
-    fn absorb(&mut self, data: &[u8]) {
-        for b in data {
-            self.state[self.pos as usize] ^= b;
-            self.advance_pos();
+    /// How many bytes of an operation on `len` bytes fit into the current block.
+    #[inline(always)]
+    fn block_len(&self, len: usize) -> usize {
+        len.min(usize::from(Self::R - self.pos))
+    }
+
+    /// Apply an operation to some data, one block at a time.
+    ///
+    /// The operation gets called with the current position in the state,
+    /// and the part of the data which fits into the current block.
+    #[inline(always)]
+    fn for_each_block(&mut self, mut data: &[u8], op: impl Fn(&mut KittenState<P>, usize, &[u8])) {
+        while !data.is_empty() {
+            let (now, rest) = data.split_at(self.block_len(data.len()));
+            op(&mut self.state, self.pos as usize, now);
+            self.advance_pos(now.len());
+            data = rest;
         }
     }
 
-    /// Absorb data into the sponge, and the set the data to the resulting output.
-    fn absorb_and_set(&mut self, data: &mut [u8]) {
-        for b in data {
-            self.state[self.pos as usize] ^= *b;
-            *b = self.state[self.pos as usize];
-            self.advance_pos();
+    /// Apply an operation to some mutable data, one block at a time.
+    ///
+    /// This is the same as `for_each_block`, except that the data can be modified.
+    #[inline(always)]
+    fn for_each_block_mut(
+        &mut self,
+        mut data: &mut [u8],
+        op: impl Fn(&mut KittenState<P>, usize, &mut [u8]),
+    ) {
+        while !data.is_empty() {
+            let len = self.block_len(data.len());
+            let (now, rest) = core::mem::take(&mut data).split_at_mut(len);
+            op(&mut self.state, self.pos as usize, now);
+            self.advance_pos(now.len());
+            data = rest;
         }
     }
 
+    /// Absorb some data into this sponge.
+    pub(crate) fn absorb(&mut self, data: &[u8]) {
+        self.for_each_block(data, |state, pos, data| state.xor_in(pos, data));
+    }
+
+    /// Absorb data into the sponge, and the set the data to the resulting output.
+    pub(crate) fn absorb_and_set(&mut self, data: &mut [u8]) {
+        self.for_each_block_mut(data, |state, pos, data| state.xor_in_out(pos, data));
+    }
+
     /// Overwrite bytes of the state with this data.
-    fn overwrite(&mut self, data: &[u8]) {
-        for &b in data {
-            self.state[self.pos as usize] = b;
-            self.advance_pos();
-        }
+    pub(crate) fn overwrite(&mut self, data: &[u8]) {
+        self.for_each_block(data, |state, pos, data| state.write(pos, data));
     }
 
     /// Zero out bytes of the state.
     ///
     /// A special case of `overwrite`.
-    fn zero_out(&mut self, len: usize) {
-        for _ in 0..len {
-            self.state[self.pos as usize] = 0;
-            self.advance_pos();
+    pub(crate) fn zero_out(&mut self, mut len: usize) {
+        while len > 0 {
+            let now = self.block_len(len);
+            self.state.zero(self.pos as usize, now);
+            self.advance_pos(now);
+            len -= now;
         }
     }
 
@@ -407,22 +833,13 @@ impl Meow {
     ///
     /// You can accomplish this by setting the state to the initial value of the data,
     /// which was the ciphertext.
-    fn exchange(&mut self, data: &mut [u8]) {
-        for b in data {
-            let pos = self.pos as usize;
-            *b ^= self.state[pos];
-            self.state[pos] ^= *b;
-            self.advance_pos();
-        }
+    pub(crate) fn exchange(&mut self, data: &mut [u8]) {
+        self.for_each_block_mut(data, |state, pos, data| state.exchange(pos, data));
     }
 
     /// Copy bytes from the state.
-    fn copy(&mut self, data: &mut [u8]) {
-        for b in data {
-            let pos = self.pos as usize;
-            *b = self.state[pos];
-            self.advance_pos();
-        }
+    pub(crate) fn copy(&mut self, data: &mut [u8]) {
+        self.for_each_block_mut(data, |state, pos, data| state.read(pos, data));
     }
 
     /// Squeeze bytes from the state.
@@ -431,56 +848,101 @@ impl Meow {
     /// overwriting data with 0. This can provide some forward secrecy,
     /// which is why we prefer this operation for extracting randomness
     /// from the state.
-    fn squeeze(&mut self, data: &mut [u8]) {
-        for b in data {
-            let pos = self.pos as usize;
-            *b = self.state[pos];
-            self.state[pos] = 0;
-            self.advance_pos();
-        }
+    pub(crate) fn squeeze(&mut self, data: &mut [u8]) {
+        self.for_each_block_mut(data, |state, pos, data| state.take(pos, data));
     }
 
     /// See: 7.3. Beginning an Operation.
-    fn begin_op(&mut self, flags: Flags, more: bool) {
+    ///
+    /// The length of the data the operation will process is only used for tracing.
+    ///
+    /// This panics if the operation is misused, see `try_begin_op`.
+    pub(crate) fn begin_op(&mut self, flags: Flags, more: bool, len: usize) {
+        self.try_begin_op(flags, more, len)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Begin an operation, returning an error if the operation is misused.
+    ///
+    /// The state is left unchanged if this returns an error.
+    pub(crate) fn try_begin_op(
+        &mut self,
+        flags: Flags,
+        more: bool,
+        len: usize,
+    ) -> Result<(), MeowError> {
         if more {
-            assert_eq!(
-                self.cur_flags, flags,
-                "Cannot continue {:#b} with {:#b}.",
-                self.cur_flags, flags
-            );
-            return;
+            if self.cur_flags != flags {
+                return Err(MeowError::Continuation {
+                    previous: self.cur_flags,
+                    current: flags,
+                });
+            }
+            let _flags = self.with_role(flags);
+            #[cfg(feature = "trace")]
+            self.trace.record(&self.state, _flags, true, len, self.role);
+            #[cfg(not(feature = "trace"))]
+            let _ = len;
+            return Ok(());
         }
         self.cur_flags = flags;
 
-        let flags = if flags & FLAG_T != 0 {
+        if flags & FLAG_T != 0 {
             if let Role::Undecided = self.role {
                 self.role = Role::from(flags & FLAG_I);
             }
+        }
+        let flags = self.with_role(flags);
+        #[cfg(feature = "trace")]
+        self.trace.record(&self.state, flags, false, len, self.role);
+        #[cfg(not(feature = "trace"))]
+        let _ = len;
+
+        let header = self.op_header(flags);
+        self.absorb(&header);
+
+        if Self::forces_f(flags) && !self.block_empty() {
+            self.run_f();
+        }
+        Ok(())
+    }
+
+    /// Adjust the flags of a transport operation to include our role.
+    ///
+    /// Beginning a transport operation always decides our role, so a
+    /// transport operation can't be continued while it's undecided.
+    fn with_role(&self, flags: Flags) -> Flags {
+        if flags & FLAG_T != 0 {
             flags ^ self.role.to_flag()
         } else {
             flags
-        };
+        }
+    }
 
+    /// Prepare to begin an operation, returning the header bytes to absorb.
+    ///
+    /// The flags should already include our role.
+    pub(crate) fn op_header(&mut self, flags: Flags) -> [u8; 2] {
         let old_begin = self.pos_begin;
         self.pos_begin = self.pos + 1;
+        [old_begin, flags]
+    }
 
-        self.absorb(&[old_begin, flags]);
-
-        let force_f = (flags & (FLAG_C | FLAG_K)) != 0;
-        if force_f && self.pos != 0 {
-            self.run_f();
-        }
+    /// Check whether an operation with these flags needs to run F after its header.
+    pub(crate) fn forces_f(flags: Flags) -> bool {
+        (flags & (FLAG_C | FLAG_K)) != 0
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::permutation::{KeccakF1600, TurboShake};
 
     #[test]
     fn test_basic_encryption() {
         let key = [0xAA; 32];
-        let message0 = [0xFF; MEOW_R as usize];
+        let message0 = [0xFF; rate(128) as usize];
 
         let mut encrypted = message0;
         {
@@ -505,7 +967,7 @@ mod test {
     fn test_encryption_with_nonce() {
         let key = [0xAA; 32];
         let nonce = [0xBB; 32];
-        let message0 = [0xFF; MEOW_R as usize];
+        let message0 = [0xFF; rate(128) as usize];
 
         let mut encrypted = message0.to_owned();
         {
@@ -532,7 +994,7 @@ mod test {
     fn test_authenticated_encryption() {
         let key = [0xAA; 32];
         let nonce = [0xBB; 32];
-        let message0 = [0xFF; MEOW_R as usize];
+        let message0 = [0xFF; rate(128) as usize];
 
         let mut mac = [0u8; 32];
 
@@ -601,4 +1063,313 @@ mod test {
 
         assert_eq!(hash0, hash1);
     }
+
+    #[test]
+    fn test_permutations_are_domain_separated() {
+        fn hash<P: Permutation>() -> [u8; 32] {
+            let mut meow = Meow::<128, P>::init(b"test protocol");
+            meow.ad(b"hello world!", false);
+            let mut out = [0u8; 32];
+            meow.prf(&mut out, false);
+            out
+        }
+
+        let hash0 = hash::<KitTen>();
+        let hash1 = hash::<TurboShake>();
+        let hash2 = hash::<KeccakF1600>();
+        assert_ne!(hash0, hash1);
+        assert_ne!(hash1, hash2);
+        assert_ne!(hash0, hash2);
+        assert_eq!(hash0, hash::<KitTen>());
+    }
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Run a fixed transcript touching most operations, returning the outputs.
+    ///
+    /// Most of the inputs are longer than a block, so that we cross block boundaries.
+    fn known_answer_transcript() -> Vec<u8> {
+        let data = |len: usize| -> Vec<u8> { (0..len).map(|i| (7 * i + 3) as u8).collect() };
+        let mut out = Vec::new();
+
+        let mut meow = Meow::new(b"known answer test");
+        meow.ad(&data(300), false);
+        meow.key(&[0x42; 32], false);
+        meow.send_clr(&data(50), false);
+        let mut ciphertext = data(250);
+        meow.send_enc(&mut ciphertext, false);
+        out.extend_from_slice(&ciphertext[..16]);
+        out.extend_from_slice(&ciphertext[234..]);
+        let mut plaintext = data(200);
+        meow.recv_enc(&mut plaintext, false);
+        out.extend_from_slice(&plaintext[184..]);
+        let mut prf = [0u8; 32];
+        meow.prf(&mut prf, false);
+        out.extend_from_slice(&prf);
+        meow.ratchet();
+        let mut prf = [0u8; 16];
+        meow.prf(&mut prf, false);
+        out.extend_from_slice(&prf);
+        let mut mac = [0u8; 16];
+        meow.send_mac(&mut mac);
+        out.extend_from_slice(&mac);
+
+        out
+    }
+
+    /// Like `known_answer_transcript`, but with large inputs, starting at odd offsets.
+    ///
+    /// This exercises the bulk paths, which work lane by lane inside of each block.
+    fn long_known_answer_transcript() -> Vec<u8> {
+        let data = |len: usize| -> Vec<u8> { (0..len).map(|i| (13 * i + 5) as u8).collect() };
+        let mut out = Vec::new();
+
+        let mut meow = Meow::new(b"long known answer test");
+        meow.ad(&data(1001), false);
+        meow.key(&data(333), false);
+        meow.meta_ad(&data(3), false);
+        let mut ciphertext = data(1005);
+        meow.send_enc(&mut ciphertext, false);
+        out.extend_from_slice(&ciphertext[989..]);
+        meow.recv_clr(&data(517), false);
+        let mut plaintext = data(997);
+        meow.recv_enc(&mut plaintext, false);
+        out.extend_from_slice(&plaintext[981..]);
+        meow.ratchet_many(411, false);
+        let mut prf = vec![0u8; 1003];
+        meow.prf(&mut prf, false);
+        out.extend_from_slice(&prf[987..]);
+        let mut mac = [0u8; 16];
+        meow.send_mac(&mut mac);
+        out.extend_from_slice(&mac);
+
+        out
+    }
+
+    #[test]
+    fn test_long_known_answer() {
+        // These values were computed before switching to the bulk paths,
+        // which need to produce exactly the same output.
+        assert_eq!(
+            hex(&long_known_answer_transcript()),
+            concat!(
+                "69e458e397dc3ac33eb5579aff9802bee8c6710a4c1f35aa04655bc7a932c323",
+                "d8efbb1e40e62e57e496aeccca3edefbdd5ece0ad270e81e1c7a88f7bdc062b5",
+            )
+        );
+    }
+
+    #[test]
+    fn test_known_answer() {
+        // These values pin the output of the default instance, so that we notice
+        // any change in behavior.
+        assert_eq!(
+            hex(&known_answer_transcript()),
+            concat!(
+                "46cf88fdf280af9e316cee419ccd0cc9fc0dd7481c1fbac1f97660a0bbb35711",
+                "b40d450a76d720e7360da9fdd5935f072f2a18d1165ca4dc30e9f651ef6ccce7",
+                "dbf98b572adf9a6e1ddcf85e7356c1b64fbe9ff739e2aa613fe4586c4aedace5",
+                "73a96bd69d39b965c51b1144549df7de",
+            )
+        );
+    }
+
+    #[test]
+    fn test_key_tree() {
+        let key = [0xAB; 32];
+        let message = b"hello world!";
+
+        let mut meow0 = Meow::new(b"key tree test");
+        meow0.key_tree(&key, false);
+        let mut encrypted = message.to_vec();
+        meow0.send_enc(&mut encrypted, false);
+        let mut mac = [0u8; 32];
+        meow0.send_mac(&mut mac);
+
+        let mut meow1 = Meow::new(b"key tree test");
+        meow1.key_tree(&key[..7], false);
+        meow1.key_tree(&key[7..], true);
+        meow1.recv_enc(&mut encrypted, false);
+        assert_eq!(&encrypted, message);
+        assert!(meow1.recv_mac(&mut mac).is_ok());
+
+        // Using a different key, or the regular key operation, shouldn't match.
+        let mut other_key = key;
+        other_key[31] ^= 0x80;
+        let mut prfs = Vec::new();
+        for op in 0..3 {
+            let mut meow = Meow::new(b"key tree test");
+            match op {
+                0 => meow.key_tree(&key, false),
+                1 => meow.key_tree(&other_key, false),
+                _ => meow.key(&key, false),
+            }
+            let mut prf = [0u8; 32];
+            meow.prf(&mut prf, false);
+            prfs.push(prf);
+        }
+        assert_ne!(prfs[0], prfs[1]);
+        assert_ne!(prfs[0], prfs[2]);
+
+        let mut meow = Meow::new(b"key tree test");
+        meow.key_tree(b"key", false);
+        let mut prf = [0u8; 32];
+        meow.prf(&mut prf, false);
+        assert_eq!(
+            hex(&prf),
+            "404adc29676309e9efe0448e5a50ae452bdc3d60df9a081722f8062cfef25e3f"
+        );
+    }
+
+    #[test]
+    fn test_security_levels_are_domain_separated() {
+        let mut hash0 = [0u8; 32];
+        {
+            let mut meow = Meow::<128>::init(b"test protocol");
+            meow.ad(b"hello world!", false);
+            meow.prf(&mut hash0, false);
+        }
+
+        let mut hash1 = [0u8; 32];
+        {
+            let mut meow = Meow::<256>::init(b"test protocol");
+            meow.ad(b"hello world!", false);
+            meow.prf(&mut hash1, false);
+        }
+
+        assert_ne!(hash0, hash1);
+    }
+
+    #[test]
+    fn test_basic_encryption_256() {
+        let key = [0xAA; 64];
+        let message0 = [0xFF; 2 * rate(256) as usize];
+
+        let mut encrypted = message0;
+        {
+            let mut meow = Meow::<256>::init(b"test protocol");
+            meow.key(&key, false);
+            meow.send_enc(&mut encrypted, false);
+        }
+
+        assert_ne!(message0, encrypted);
+
+        let mut message1 = encrypted;
+        {
+            let mut meow = Meow::<256>::init(b"test protocol");
+            meow.key(&key, false);
+            meow.recv_enc(&mut message1, false);
+        }
+
+        assert_eq!(message0, message1);
+    }
+
+    #[test]
+    fn test_custom_permutation() {
+        // Deliberately not `Copy` or `Debug`, to check that `Clone` is all we need.
+        #[derive(Clone)]
+        struct LongName;
+
+        impl Permutation for LongName {
+            const NAME: &'static [u8] = &[0xAB; MAX_PERMUTATION_NAME_LEN];
+
+            fn permute(state: &mut [u64; crate::kitten::STATE_SIZE_U64]) {
+                keccak::keccak_p(state, 10);
+            }
+        }
+
+        let meow = Meow::<128, LongName>::init(b"test protocol");
+        assert_eq!(meow.clone().state_bytes(), meow.state_bytes());
+        let meow = Meow::<256, LongName>::init(b"test protocol");
+        assert_eq!(meow.clone().state_bytes(), meow.state_bytes());
+    }
+
+    #[test]
+    fn test_try_continuation_mismatch() {
+        let mut meow = Meow::new(b"fallible test");
+        meow.ad(b"some data", false);
+        let before = meow.state_bytes();
+        assert_eq!(
+            meow.try_send_clr(b"more data", true),
+            Err(MeowError::Continuation {
+                previous: FLAG_A,
+                current: FLAG_A | FLAG_T
+            })
+        );
+        // The failed operation shouldn't have changed anything.
+        assert_eq!(meow.state_bytes(), before);
+        assert_eq!(meow.try_ad(b"more data", true), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot continue 0b10 with 0b1010.")]
+    fn test_continuation_mismatch_panics() {
+        let mut meow = Meow::new(b"fallible test");
+        meow.ad(b"some data", false);
+        meow.send_clr(b"more data", true);
+    }
+
+    #[test]
+    fn test_try_recv_mac() {
+        let mut meow0 = Meow::new(b"fallible test");
+        let mut meow1 = meow0.clone();
+        let mut mac = [0u8; 16];
+        assert_eq!(meow0.try_send_mac(&mut mac), Ok(()));
+        let mut bad_mac = mac;
+        bad_mac[0] ^= 1;
+        assert_eq!(
+            meow1.clone().try_recv_mac(&mut bad_mac),
+            Err(MeowError::Mac)
+        );
+        assert!(meow1.clone().recv_mac(&mut bad_mac.clone()).is_err());
+        assert_eq!(meow1.try_recv_mac(&mut mac), Ok(()));
+    }
+
+    #[test]
+    fn test_streaming_matches_at_every_split() {
+        let data: Vec<u8> = (0..3 * rate(128) as usize).map(|i| i as u8).collect();
+
+        // Run the same operations, splitting each of them into chunks of a given size.
+        let run = |split: usize| -> Vec<u8> {
+            let mut out = Vec::new();
+            let mut meow = Meow::new(b"test protocol");
+            for (i, chunk) in data.chunks(split).enumerate() {
+                meow.ad(chunk, i > 0);
+            }
+            for (i, chunk) in data.chunks(split).enumerate() {
+                meow.key(chunk, i > 0);
+            }
+            let mut buf = data.clone();
+            for (i, chunk) in buf.chunks_mut(split).enumerate() {
+                meow.send_enc(chunk, i > 0);
+            }
+            out.extend_from_slice(&buf);
+            let mut buf = data.clone();
+            for (i, chunk) in buf.chunks_mut(split).enumerate() {
+                meow.recv_enc(chunk, i > 0);
+            }
+            out.extend_from_slice(&buf);
+            let mut buf = data.clone();
+            for (i, chunk) in buf.chunks_mut(split).enumerate() {
+                meow.prf(chunk, i > 0);
+            }
+            out.extend_from_slice(&buf);
+            out
+        };
+
+        let expected = run(data.len());
+        for split in [
+            1,
+            7,
+            8,
+            9,
+            63,
+            rate(128) as usize - 1,
+            rate(128) as usize + 5,
+        ] {
+            assert_eq!(expected, run(split), "split = {}", split);
+        }
+    }
 }
@@ -0,0 +1,440 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use base64ct::{Base64Unpadded, Encoding};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::{framing::frame, meow::Meow};
+
+/// The protocol string used by [`MeowBalloon`].
+const PASSWORD_PROTOCOL: &[u8] = b"ck-meow balloon";
+/// The identifier of the algorithm in PHC strings.
+const ALGORITHM_ID: &str = "meow-balloon";
+/// The version of the algorithm, included in PHC strings.
+const VERSION: u32 = 1;
+/// The number of bytes in each block of the buffer.
+const BLOCK_LEN: usize = 32;
+/// The number of blocks in each KiB of memory.
+const BLOCKS_PER_KIB: usize = 1024 / BLOCK_LEN;
+/// The number of other blocks mixed into each block, in each round.
+const DELTA: u64 = 3;
+/// The number of bytes in the hash, when encoded as a PHC string.
+const HASH_LEN: usize = 32;
+/// The smallest number of bytes allowed in a salt.
+const MIN_SALT_LEN: usize = 8;
+
+/// An error produced when hashing or verifying a password fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PasswordError {
+    /// One of the cost parameters is out of range.
+    Params,
+    /// The salt is too short.
+    Salt,
+    /// A PHC string is malformed, or uses a different algorithm.
+    Format,
+    /// The password doesn't match the hash.
+    Verification,
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Params => write!(f, "Invalid password hashing parameters."),
+            PasswordError::Salt => write!(f, "Salt is too short."),
+            PasswordError::Format => write!(f, "Invalid password hash string."),
+            PasswordError::Verification => write!(f, "Password doesn't match the hash."),
+        }
+    }
+}
+
+impl core::error::Error for PasswordError {}
+
+/// A memory-hard password hash, following the design of Balloon hashing,
+/// built on top of Meow.
+///
+/// The hash is configured by three parameters:
+///
+/// - `t_cost`, the number of passes over the memory,
+/// - `m_cost`, the amount of memory used by each lane, in KiB,
+/// - `p_cost`, the number of independent lanes.
+///
+/// With the `std` feature, the lanes are filled in parallel, using one thread
+/// for each lane. Otherwise, they're filled one after the other.
+///
+/// Hashes are usually stored as PHC strings, which include the parameters
+/// and the salt, so that they can be checked later:
+///
+/// ```
+/// use ck_meow::MeowBalloon;
+///
+/// let balloon = MeowBalloon::new(1, 8, 1).unwrap();
+/// let hash = balloon.hash(b"hunter2", b"some random salt").unwrap();
+/// assert!(MeowBalloon::verify(b"hunter2", &hash).is_ok());
+/// assert!(MeowBalloon::verify(b"hunter3", &hash).is_err());
+/// assert!(!balloon.needs_rehash(&hash));
+/// ```
+///
+/// The salt should be random, unique to each password, and at least 8 bytes long.
+///
+/// Every block of memory is a `prf` output from a copy of a state which has
+/// absorbed the parameters and the salt with `meta_ad`, and the password
+/// with `key`. The blocks to mix together are chosen using a state which hasn't
+/// absorbed the password, so the memory access pattern doesn't depend on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeowBalloon {
+    t_cost: u32,
+    m_cost: u32,
+    p_cost: u32,
+}
+
+impl Default for MeowBalloon {
+    fn default() -> Self {
+        Self {
+            t_cost: 3,
+            m_cost: 4096,
+            p_cost: 1,
+        }
+    }
+}
+
+impl MeowBalloon {
+    /// The largest number of passes over the memory allowed.
+    pub const MAX_T_COST: u32 = 1024;
+    /// The largest amount of memory allowed, in KiB, summed over all of the lanes.
+    pub const MAX_M_COST: u32 = 1 << 22;
+    /// The largest number of lanes allowed.
+    pub const MAX_P_COST: u32 = 255;
+
+    /// Create a password hash with the given time, memory, and parallelism costs.
+    ///
+    /// All of the costs need to be at least 1. The number of passes can be
+    /// at most [`Self::MAX_T_COST`], the number of lanes at most [`Self::MAX_P_COST`],
+    /// and the memory used by all of the lanes, `m_cost * p_cost`, at most
+    /// [`Self::MAX_M_COST`]. These limits also apply to PHC strings, so that
+    /// verifying a crafted hash can't exhaust our memory or time.
+    pub fn new(t_cost: u32, m_cost: u32, p_cost: u32) -> Result<Self, PasswordError> {
+        let total_m_cost = u64::from(m_cost) * u64::from(p_cost);
+        let lane_fits = u64::from(m_cost) * 1024 <= isize::MAX as u64;
+        if !(1..=Self::MAX_T_COST).contains(&t_cost)
+            || m_cost < 1
+            || !(1..=Self::MAX_P_COST).contains(&p_cost)
+            || total_m_cost > u64::from(Self::MAX_M_COST)
+            || !lane_fits
+        {
+            return Err(PasswordError::Params);
+        }
+        Ok(Self {
+            t_cost,
+            m_cost,
+            p_cost,
+        })
+    }
+
+    /// The number of passes over the memory.
+    pub fn t_cost(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// The amount of memory used by each lane, in KiB.
+    pub fn m_cost(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// The number of independent lanes.
+    pub fn p_cost(&self) -> u32 {
+        self.p_cost
+    }
+
+    /// Hash a password, writing the raw hash into an output buffer of any length.
+    pub fn hash_into(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), PasswordError> {
+        if salt.len() < MIN_SALT_LEN {
+            return Err(PasswordError::Salt);
+        }
+        let mut index_state = Meow::new(PASSWORD_PROTOCOL);
+        let mut params = [0u8; 16];
+        let costs = [VERSION, self.t_cost, self.m_cost, self.p_cost];
+        for (chunk, x) in params.chunks_exact_mut(4).zip(costs) {
+            chunk.copy_from_slice(&x.to_le_bytes());
+        }
+        index_state.meta_ad(&params, false);
+        frame(&mut index_state, salt);
+        let mut hash_state = index_state.clone();
+        hash_state.key(password, false);
+
+        let lanes = self.fill_lanes(&hash_state, &index_state);
+        for lane in lanes.iter() {
+            hash_state.ad(lane, false);
+        }
+        hash_state.prf(out, false);
+        Ok(())
+    }
+
+    /// Hash a password, producing a PHC string.
+    ///
+    /// The string contains the parameters and the salt, along with the hash,
+    /// and looks like `$meow-balloon$v=1$t=3,m=4096,p=1$<salt>$<hash>`, with
+    /// the salt and hash encoded in unpadded base64.
+    pub fn hash(&self, password: &[u8], salt: &[u8]) -> Result<String, PasswordError> {
+        let mut hash = [0u8; HASH_LEN];
+        self.hash_into(password, salt, &mut hash)?;
+        Ok(format!(
+            "${}$v={}$t={},m={},p={}${}${}",
+            ALGORITHM_ID,
+            VERSION,
+            self.t_cost,
+            self.m_cost,
+            self.p_cost,
+            Base64Unpadded::encode_string(salt),
+            Base64Unpadded::encode_string(&hash),
+        ))
+    }
+
+    /// Check a password against a PHC string produced by [`Self::hash`].
+    ///
+    /// The parameters and salt are taken from the string, and the hashes
+    /// are compared in constant time.
+    pub fn verify(password: &[u8], phc: &str) -> Result<(), PasswordError> {
+        let (balloon, salt, expected) = Self::parse(phc)?;
+        let mut actual = Zeroizing::new(vec![0u8; expected.len()]);
+        balloon.hash_into(password, &salt, &mut actual)?;
+        if !bool::from(actual.ct_eq(&expected)) {
+            return Err(PasswordError::Verification);
+        }
+        Ok(())
+    }
+
+    /// Check whether a PHC string was produced with different parameters.
+    ///
+    /// After verifying a password, this can be used to see if its hash
+    /// should be upgraded to the current parameters. Strings which can't
+    /// be parsed always need to be rehashed.
+    pub fn needs_rehash(&self, phc: &str) -> bool {
+        match Self::parse(phc) {
+            Ok((balloon, _, hash)) => balloon != *self || hash.len() != HASH_LEN,
+            Err(_) => true,
+        }
+    }
+
+    /// Parse a PHC string into its parameters, salt, and hash.
+    fn parse(phc: &str) -> Result<(Self, Vec<u8>, Vec<u8>), PasswordError> {
+        let parts: Vec<&str> = phc.split('$').collect();
+        let ["", ALGORITHM_ID, version, params, salt, hash] = parts[..] else {
+            return Err(PasswordError::Format);
+        };
+        if version != format!("v={}", VERSION) {
+            return Err(PasswordError::Format);
+        }
+        let mut costs = [0u32; 3];
+        let mut params = params.split(',');
+        for (cost, name) in costs.iter_mut().zip(["t=", "m=", "p="]) {
+            *cost = params
+                .next()
+                .and_then(|param| param.strip_prefix(name))
+                .filter(|value| !value.starts_with('+'))
+                .and_then(|value| value.parse().ok())
+                .ok_or(PasswordError::Format)?;
+        }
+        if params.next().is_some() {
+            return Err(PasswordError::Format);
+        }
+        let balloon = Self::new(costs[0], costs[1], costs[2])?;
+        let salt = Base64Unpadded::decode_vec(salt).map_err(|_| PasswordError::Format)?;
+        let hash = Base64Unpadded::decode_vec(hash).map_err(|_| PasswordError::Format)?;
+        if hash.is_empty() {
+            return Err(PasswordError::Format);
+        }
+        Ok((balloon, salt, hash))
+    }
+
+    #[cfg(feature = "std")]
+    fn fill_lanes(&self, hash_state: &Meow, index_state: &Meow) -> Vec<[u8; BLOCK_LEN]> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.p_cost)
+                .map(|lane| scope.spawn(move || self.fill_lane(hash_state, index_state, lane)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fill_lanes(&self, hash_state: &Meow, index_state: &Meow) -> Vec<[u8; BLOCK_LEN]> {
+        (0..self.p_cost)
+            .map(|lane| self.fill_lane(hash_state, index_state, lane))
+            .collect()
+    }
+
+    /// Fill the memory of a single lane, returning its last block.
+    fn fill_lane(&self, hash_state: &Meow, index_state: &Meow, lane: u32) -> [u8; BLOCK_LEN] {
+        let mut hash_state = hash_state.clone();
+        hash_state.meta_ad(&lane.to_le_bytes(), false);
+        let mut index_state = index_state.clone();
+        index_state.meta_ad(&lane.to_le_bytes(), false);
+
+        let blocks = self.m_cost as usize * BLOCKS_PER_KIB;
+        let mut buf = Zeroizing::new(vec![[0u8; BLOCK_LEN]; blocks]);
+        let mut counter = 0u64;
+        // First, fill the buffer sequentially.
+        buf[0] = hash_block(&hash_state, &mut counter, &[]);
+        for m in 1..blocks {
+            buf[m] = hash_block(&hash_state, &mut counter, &[&buf[m - 1]]);
+        }
+        // Then, mix each block with the previous one, and a few pseudorandom others.
+        for t in 0..self.t_cost {
+            for m in 0..blocks {
+                let prev = buf[(m + blocks - 1) % blocks];
+                buf[m] = hash_block(&hash_state, &mut counter, &[&prev, &buf[m]]);
+                for i in 0..DELTA {
+                    let other = pick_block(&index_state, t, m, i, blocks);
+                    buf[m] = hash_block(&hash_state, &mut counter, &[&buf[m], &buf[other]]);
+                }
+            }
+        }
+        buf[blocks - 1]
+    }
+}
+
+/// Hash some blocks together, along with a counter, which gets incremented.
+fn hash_block(state: &Meow, counter: &mut u64, blocks: &[&[u8; BLOCK_LEN]]) -> [u8; BLOCK_LEN] {
+    let mut meow = state.clone();
+    meow.meta_ad(&counter.to_le_bytes(), false);
+    *counter += 1;
+    for block in blocks {
+        meow.ad(*block, false);
+    }
+    let mut out = [0u8; BLOCK_LEN];
+    meow.prf(&mut out, false);
+    out
+}
+
+/// Pick the index of another block to mix in, independently of the password.
+fn pick_block(index_state: &Meow, t: u32, m: usize, i: u64, blocks: usize) -> usize {
+    let mut meow = index_state.clone();
+    let mut position = [0u8; 20];
+    position[..4].copy_from_slice(&t.to_le_bytes());
+    position[4..12].copy_from_slice(&(m as u64).to_le_bytes());
+    position[12..].copy_from_slice(&i.to_le_bytes());
+    meow.meta_ad(&position, false);
+    // With 128 bits of output, the bias from reducing is negligible.
+    let mut out = [0u8; 16];
+    meow.prf(&mut out, false);
+    (u128::from_le_bytes(out) % blocks as u128) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SALT: &[u8] = b"a salt of 16 chr";
+
+    fn small() -> MeowBalloon {
+        MeowBalloon::new(1, 2, 1).unwrap()
+    }
+
+    #[test]
+    fn test_hash_is_deterministic() {
+        let mut out0 = [0u8; 32];
+        let mut out1 = [0u8; 32];
+        small().hash_into(b"password", SALT, &mut out0).unwrap();
+        small().hash_into(b"password", SALT, &mut out1).unwrap();
+        assert_eq!(out0, out1);
+    }
+
+    #[test]
+    fn test_inputs_change_hash() {
+        let hash = |balloon: MeowBalloon, password: &[u8], salt: &[u8]| {
+            let mut out = [0u8; 32];
+            balloon.hash_into(password, salt, &mut out).unwrap();
+            out
+        };
+        let base = hash(small(), b"password", SALT);
+        let others = [
+            hash(small(), b"passwore", SALT),
+            hash(small(), b"password", b"another salt"),
+            hash(MeowBalloon::new(2, 2, 1).unwrap(), b"password", SALT),
+            hash(MeowBalloon::new(1, 3, 1).unwrap(), b"password", SALT),
+            hash(MeowBalloon::new(1, 2, 2).unwrap(), b"password", SALT),
+        ];
+        for other in others {
+            assert_ne!(base, other);
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let max_m = MeowBalloon::MAX_M_COST;
+        for (t, m, p) in [
+            (0, 1, 1),
+            (1, 0, 1),
+            (1, 1, 0),
+            (1, 1, 256),
+            (MeowBalloon::MAX_T_COST + 1, 1, 1),
+            (1, max_m + 1, 1),
+            (1, max_m / 2 + 1, 2),
+        ] {
+            assert_eq!(MeowBalloon::new(t, m, p), Err(PasswordError::Params));
+        }
+        let mut out = [0u8; 32];
+        assert_eq!(
+            small().hash_into(b"password", b"short", &mut out),
+            Err(PasswordError::Salt)
+        );
+    }
+
+    #[test]
+    fn test_phc_roundtrip() {
+        let balloon = MeowBalloon::new(1, 2, 2).unwrap();
+        let phc = balloon.hash(b"password", SALT).unwrap();
+        assert!(phc.starts_with("$meow-balloon$v=1$t=1,m=2,p=2$"));
+        assert_eq!(MeowBalloon::verify(b"password", &phc), Ok(()));
+        assert_eq!(
+            MeowBalloon::verify(b"Password", &phc),
+            Err(PasswordError::Verification)
+        );
+        assert!(!balloon.needs_rehash(&phc));
+        assert!(small().needs_rehash(&phc));
+        assert!(small().needs_rehash("not a hash"));
+    }
+
+    #[test]
+    fn test_phc_rejects_malformed() {
+        let phc = small().hash(b"password", SALT).unwrap();
+        let bad = [
+            phc.replace("meow-balloon", "argon2id"),
+            phc.replace("v=1", "v=2"),
+            phc.replace("t=1,m=2,p=1", "m=2,t=1,p=1"),
+            phc.replace("t=1,m=2,p=1", "t=1,m=2"),
+            phc.replace("t=1,m=2,p=1", "t=1,m=2,p=1,x=3"),
+            phc.replace("t=1", "t=+1"),
+            phc.replace("t=1", "t=0"),
+            format!("{}$", phc),
+            format!("{}!", phc),
+            phc[1..].into(),
+        ];
+        for phc in bad {
+            assert!(MeowBalloon::verify(b"password", &phc).is_err(), "{}", phc);
+        }
+    }
+
+    #[test]
+    fn test_phc_rejects_excessive_costs() {
+        let phc = small().hash(b"password", SALT).unwrap();
+        for costs in ["t=1,m=4294967295,p=255", "t=4294967295,m=2,p=1"] {
+            let phc = phc.replace("t=1,m=2,p=1", costs);
+            assert_eq!(
+                MeowBalloon::verify(b"password", &phc),
+                Err(PasswordError::Params)
+            );
+            assert!(small().needs_rehash(&phc));
+        }
+    }
+}
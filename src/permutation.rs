@@ -0,0 +1,255 @@
+use crate::kitten::STATE_SIZE_U64;
+
+/// Represents a permutation of the 1600 bit Keccak state.
+///
+/// Meow is built around a duplex sponge, which is agnostic about the exact
+/// permutation used underneath. This trait allows choosing that permutation.
+///
+/// By default, we use KitTen, the 10 round variant of Keccak, but other variants
+/// are provided as well, if you'd like a larger security margin.
+///
+/// The name of the permutation is absorbed into the initial state, so instances
+/// using different permutations will never produce the same output.
+///
+/// Permutations are usually unit structs, and need to be cloneable so that
+/// the states using them can be cloned as well.
+pub trait Permutation: Clone {
+    /// A name uniquely identifying this permutation.
+    ///
+    /// This needs to fit into the initial block of the state, alongside our
+    /// context string, so it can be at most 118 bytes long. Using a longer
+    /// name will fail to compile.
+    const NAME: &'static [u8];
+
+    /// Apply the permutation to a state.
+    ///
+    /// The words of the state should be interpreted in native endianness.
+    fn permute(state: &mut [u64; STATE_SIZE_U64]);
+
+    /// Apply the permutation to several states at once.
+    ///
+    /// By default, this applies the permutation to each state in turn, but
+    /// implementations can instead process all of the states in lockstep,
+    /// which lets the compiler make use of SIMD instructions.
+    fn permute_many<const N: usize>(states: &mut [[u64; STATE_SIZE_U64]; N]) {
+        for state in states {
+            Self::permute(state);
+        }
+    }
+}
+
+/// The round constants of Keccak.
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation offset of each word of the state, in the rho step.
+const RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// Where each word of the state ends up, after the pi step.
+const PI: [usize; 25] = [
+    0, 10, 20, 5, 15, 16, 1, 11, 21, 6, 7, 17, 2, 12, 22, 23, 8, 18, 3, 13, 14, 24, 9, 19, 4,
+];
+
+/// One word of the state, for each of the states being permuted together.
+type Words<const N: usize> = [u64; N];
+
+#[inline(always)]
+fn xor<const N: usize>(mut a: Words<N>, b: &Words<N>) -> Words<N> {
+    for (a, b) in a.iter_mut().zip(b) {
+        *a ^= b;
+    }
+    a
+}
+
+#[inline(always)]
+fn rotate<const N: usize>(mut a: Words<N>, by: u32) -> Words<N> {
+    for a in &mut a {
+        *a = a.rotate_left(by);
+    }
+    a
+}
+
+/// Whether the target has vector instructions for rotating 64 bit words.
+///
+/// Permuting states in lockstep only pays off with these instructions, without
+/// them, permuting each state on its own ends up being faster.
+const VECTOR_ROTATES: bool = cfg!(any(
+    target_feature = "avx512f",
+    all(target_arch = "aarch64", target_feature = "sha3")
+));
+
+/// Apply the last `rounds` rounds of Keccak to several states, as fast as possible.
+fn permute_many_with<const N: usize>(
+    states: &mut [[u64; STATE_SIZE_U64]; N],
+    rounds: usize,
+    permute: fn(&mut [u64; STATE_SIZE_U64]),
+) {
+    if VECTOR_ROTATES {
+        keccak_p_many(states, rounds);
+    } else {
+        for state in states {
+            permute(state);
+        }
+    }
+}
+
+/// Apply the last `rounds` rounds of Keccak to several states in lockstep.
+///
+/// The states are interleaved, so that each word of the state holds that word
+/// for every state, which lets each step operate on all of them at once.
+fn keccak_p_many<const N: usize>(states: &mut [[u64; STATE_SIZE_U64]; N], rounds: usize) {
+    let mut a = [[0u64; N]; STATE_SIZE_U64];
+    for (l, state) in states.iter().enumerate() {
+        for (i, &word) in state.iter().enumerate() {
+            a[i][l] = word;
+        }
+    }
+
+    for &rc in &ROUND_CONSTANTS[24 - rounds..] {
+        // Theta
+        let mut c = [[0u64; N]; 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = xor(
+                xor(xor(xor(a[x], &a[x + 5]), &a[x + 10]), &a[x + 15]),
+                &a[x + 20],
+            );
+        }
+        for x in 0..5 {
+            let d = xor(c[(x + 4) % 5], &rotate(c[(x + 1) % 5], 1));
+            for y in 0..5 {
+                a[x + 5 * y] = xor(a[x + 5 * y], &d);
+            }
+        }
+        // Rho and Pi
+        let mut b = [[0u64; N]; STATE_SIZE_U64];
+        for i in 0..STATE_SIZE_U64 {
+            b[PI[i]] = rotate(a[i], RHO[i]);
+        }
+        // Chi
+        for y in 0..5 {
+            for x in 0..5 {
+                let (b1, b2) = (&b[(x + 1) % 5 + 5 * y], &b[(x + 2) % 5 + 5 * y]);
+                for l in 0..N {
+                    a[x + 5 * y][l] = b[x + 5 * y][l] ^ (!b1[l] & b2[l]);
+                }
+            }
+        }
+        // Iota
+        for word in &mut a[0] {
+            *word ^= rc;
+        }
+    }
+
+    for (l, state) in states.iter_mut().enumerate() {
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = a[i][l];
+        }
+    }
+}
+
+/// The KitTen permutation.
+///
+/// This is a reduced round version of the Keccak permutation, using only 10 rounds.
+/// This is the permutation used by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KitTen;
+
+impl Permutation for KitTen {
+    const NAME: &'static [u8] = b"KitTen";
+
+    fn permute(state: &mut [u64; STATE_SIZE_U64]) {
+        keccak::keccak_p(state, 10);
+    }
+
+    fn permute_many<const N: usize>(states: &mut [[u64; STATE_SIZE_U64]; N]) {
+        permute_many_with(states, 10, Self::permute);
+    }
+}
+
+/// The 12 round Keccak permutation.
+///
+/// This is the same permutation used by TurboSHAKE and KangarooTwelve.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TurboShake;
+
+impl Permutation for TurboShake {
+    const NAME: &'static [u8] = b"TurboSHAKE";
+
+    fn permute(state: &mut [u64; STATE_SIZE_U64]) {
+        keccak::keccak_p(state, 12);
+    }
+
+    fn permute_many<const N: usize>(states: &mut [[u64; STATE_SIZE_U64]; N]) {
+        permute_many_with(states, 12, Self::permute);
+    }
+}
+
+/// The full 24 round Keccak-f\[1600\] permutation.
+///
+/// This is the permutation used by SHA-3 and the original STROBE.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeccakF1600;
+
+impl Permutation for KeccakF1600 {
+    const NAME: &'static [u8] = b"Keccak-f[1600]";
+
+    fn permute(state: &mut [u64; STATE_SIZE_U64]) {
+        keccak::f1600(state);
+    }
+
+    fn permute_many<const N: usize>(states: &mut [[u64; STATE_SIZE_U64]; N]) {
+        permute_many_with(states, 24, Self::permute);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_permute_many<P: Permutation>(rounds: usize) {
+        let mut states: [[u64; STATE_SIZE_U64]; 4] =
+            core::array::from_fn(|l| core::array::from_fn(|i| (l * 1000 + i) as u64));
+        let mut expected = states;
+        for state in &mut expected {
+            P::permute(state);
+        }
+        let mut interleaved = states;
+        P::permute_many(&mut states);
+        assert_eq!(states, expected);
+        keccak_p_many(&mut interleaved, rounds);
+        assert_eq!(interleaved, expected);
+    }
+
+    #[test]
+    fn test_permute_many_matches_permute() {
+        check_permute_many::<KitTen>(10);
+        check_permute_many::<TurboShake>(12);
+        check_permute_many::<KeccakF1600>(24);
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+use rand_core::{impls, CryptoRng, Error, RngCore, SeedableRng};
+#[cfg(feature = "getrandom")]
+use zeroize::Zeroizing;
+
+use crate::meow::Meow;
+
+/// The protocol string used by [`MeowRng`].
+const RNG_PROTOCOL: &[u8] = b"ck-meow RNG";
+
+/// A cryptographically secure random number generator built on top of Meow.
+///
+/// This implements the traits from the `rand_core` crate. Seeding the generator
+/// includes the seed with `key`, and each request squeezes out its output with `prf`,
+/// before calling `ratchet`. This means that compromising the state of the generator
+/// doesn't reveal anything it produced before.
+///
+/// Seeding the generator with a fixed seed makes its output deterministic,
+/// which is useful for tests:
+///
+/// ```
+/// use ck_meow::MeowRng;
+/// use rand_core::{RngCore, SeedableRng};
+///
+/// let mut rng0 = MeowRng::from_seed([0xAA; 32]);
+/// let mut rng1 = MeowRng::from_seed([0xAA; 32]);
+/// assert_eq!(rng0.next_u64(), rng1.next_u64());
+/// ```
+///
+/// More entropy can be mixed in at any time with [`Self::reseed`].
+///
+/// With the `getrandom` feature, [`Self::from_os_rng`] seeds a generator
+/// using the operating system. That generator also checks whether the
+/// process has forked, before each request, and reseeds itself if so,
+/// so that the parent and child processes never produce the same output.
+#[derive(Clone)]
+pub struct MeowRng {
+    meow: Meow,
+    /// The process which seeded this generator, if seeded from the OS.
+    #[cfg(feature = "getrandom")]
+    pid: Option<u32>,
+}
+
+impl MeowRng {
+    /// Mix more entropy into the state of the generator.
+    ///
+    /// The output after reseeding depends on both the original seed, and this entropy.
+    pub fn reseed(&mut self, entropy: &[u8]) {
+        self.meow.key(entropy, false);
+        self.meow.ratchet();
+    }
+
+    /// Create a generator seeded by the operating system.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the operating system fails to provide randomness.
+    /// See [`Self::try_from_os_rng`] for a version returning an error instead.
+    #[cfg(feature = "getrandom")]
+    pub fn from_os_rng() -> Self {
+        Self::try_from_os_rng().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`Self::from_os_rng`], but returning an error instead of panicking.
+    #[cfg(feature = "getrandom")]
+    pub fn try_from_os_rng() -> Result<Self, Error> {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        OsRng.try_fill_bytes(&mut seed[..])?;
+        let mut out = Self::from_seed(*seed);
+        out.pid = Some(std::process::id());
+        Ok(out)
+    }
+
+    /// Mix randomness from the operating system into the state of the generator.
+    #[cfg(feature = "getrandom")]
+    pub fn reseed_from_os_rng(&mut self) -> Result<(), Error> {
+        let mut entropy = Zeroizing::new([0u8; 32]);
+        OsRng.try_fill_bytes(&mut entropy[..])?;
+        self.reseed(&entropy[..]);
+        Ok(())
+    }
+
+    /// Reseed the generator if we're in a different process than the one that seeded it.
+    #[cfg(feature = "getrandom")]
+    fn check_fork(&mut self) -> Result<(), Error> {
+        let pid = std::process::id();
+        if matches!(self.pid, Some(seeded) if seeded != pid) {
+            self.reseed_from_os_rng()?;
+            self.pid = Some(pid);
+        }
+        Ok(())
+    }
+
+    fn generate(&mut self, dest: &mut [u8]) {
+        self.meow.prf(dest, false);
+        self.meow.ratchet();
+    }
+}
+
+impl SeedableRng for MeowRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut meow = Meow::new(RNG_PROTOCOL);
+        meow.key(&seed, false);
+        Self {
+            meow,
+            #[cfg(feature = "getrandom")]
+            pid: None,
+        }
+    }
+}
+
+impl RngCore for MeowRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        #[cfg(feature = "getrandom")]
+        self.check_fork()?;
+        self.generate(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for MeowRng {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let mut meow = Meow::new(RNG_PROTOCOL);
+        meow.key(&[1u8; 32], false);
+        let mut expected = [0u8; 2];
+        meow.prf(&mut expected[..1], false);
+        meow.ratchet();
+        meow.prf(&mut expected[1..], false);
+
+        let mut rng = MeowRng::from_seed([1u8; 32]);
+        let mut out = [0u8; 2];
+        rng.fill_bytes(&mut out[..1]);
+        rng.fill_bytes(&mut out[1..]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut rng0 = MeowRng::seed_from_u64(7);
+        let mut rng1 = MeowRng::seed_from_u64(7);
+        let mut rng2 = MeowRng::seed_from_u64(8);
+        for _ in 0..10 {
+            let x = rng0.next_u64();
+            assert_eq!(x, rng1.next_u64());
+            assert_ne!(x, rng2.next_u64());
+        }
+        assert_eq!(rng0.next_u32(), rng1.next_u32());
+    }
+
+    #[test]
+    fn test_reseed() {
+        let mut rng0 = MeowRng::from_seed([1u8; 32]);
+        let mut rng1 = rng0.clone();
+        rng0.reseed(b"entropy");
+        assert_ne!(rng0.next_u64(), rng1.next_u64());
+        rng1.reseed(b"entropy");
+        let mut rng2 = MeowRng::from_seed([1u8; 32]);
+        rng2.next_u64();
+        rng2.reseed(b"entropy");
+        assert_eq!(rng1.next_u64(), rng2.next_u64());
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_fork_detection() {
+        let mut rng = MeowRng::from_os_rng();
+        assert_eq!(rng.pid, Some(std::process::id()));
+        let mut same_process = rng.clone();
+        assert_eq!(rng.next_u64(), same_process.next_u64());
+
+        // Pretend that we were seeded in some other process.
+        let mut forked = rng.clone();
+        forked.pid = Some(std::process::id().wrapping_add(1));
+        assert_ne!(rng.next_u64(), forked.next_u64());
+        assert_eq!(forked.pid, Some(std::process::id()));
+
+        // Deterministic generators never reseed themselves.
+        assert_eq!(MeowRng::from_seed([1u8; 32]).pid, None);
+    }
+}
@@ -0,0 +1,280 @@
+use core::ops::Range;
+
+use crate::{
+    meow::{Meow, Security, SupportedSecurity},
+    permutation::Permutation,
+};
+
+/// Check whether `a < b`, in constant time, with both numbers in little endian.
+fn less_than_le<'a>(a: impl Iterator<Item = &'a u8>, b: impl Iterator<Item = &'a u8>) -> bool {
+    let mut borrow = 0u16;
+    for (&a, &b) in a.zip(b) {
+        let diff = u16::from(a).wrapping_sub(u16::from(b)).wrapping_sub(borrow);
+        borrow = diff >> 15;
+    }
+    borrow == 1
+}
+
+/// Find how many bytes to squeeze to sample integers below a modulus, in little endian,
+/// along with the mask to apply to the last byte.
+///
+/// This squeezes exactly as many bits as `modulus - 1` has.
+fn sample_shape<'a>(modulus: impl Iterator<Item = &'a u8> + Clone) -> (usize, u8) {
+    let (top, top_byte) = modulus
+        .clone()
+        .enumerate()
+        .filter(|(_, &b)| b != 0)
+        .last()
+        .expect("modulus must not be zero");
+    let power_of_two = modulus.map(|b| b.count_ones()).sum::<u32>() == 1;
+    let bits = 8 * top + 8 - top_byte.leading_zeros() as usize - usize::from(power_of_two);
+    let mask = match bits % 8 {
+        0 => 0xFF,
+        extra => (1 << extra) - 1,
+    };
+    (bits.div_ceil(8), mask)
+}
+
+impl<const SECURITY: usize, P: Permutation> Meow<SECURITY, P>
+where
+    Security<SECURITY>: SupportedSecurity,
+{
+    /// Squeeze out a uniformly random integer below a modulus, in little endian.
+    ///
+    /// The output needs to be the same length as the modulus. This uses
+    /// rejection sampling: we squeeze out as many bits as the largest allowed
+    /// value has, and try again if the result is too large, so there's no bias at all.
+    /// Each attempt succeeds with probability at least 1/2.
+    ///
+    /// All of the attempts together form a single `prf` operation. Two parties
+    /// with the same state will always make the same number of attempts.
+    ///
+    /// ```
+    /// use ck_meow::Meow;
+    ///
+    /// // The order of the Ristretto255 group.
+    /// let order: [u8; 32] = [
+    ///     0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
+    ///     0xde, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ///     0x00, 0x00, 0x00, 0x10,
+    /// ];
+    /// let mut meow = Meow::new(b"my protocol");
+    /// meow.ad(b"some data", false);
+    /// let mut challenge = [0u8; 32];
+    /// meow.prf_below_le(&order, &mut challenge);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This panics if the modulus is zero, or if the lengths don't match.
+    pub fn prf_below_le(&mut self, modulus: &[u8], out: &mut [u8]) {
+        assert_eq!(
+            modulus.len(),
+            out.len(),
+            "modulus and output lengths differ"
+        );
+        let (len, mask) = sample_shape(modulus.iter());
+        out.fill(0);
+        if len == 0 {
+            return;
+        }
+        let mut more = false;
+        loop {
+            self.prf(&mut out[..len], more);
+            more = true;
+            out[len - 1] &= mask;
+            if less_than_le(out.iter(), modulus.iter()) {
+                return;
+            }
+        }
+    }
+
+    /// Squeeze out a uniformly random integer below a modulus, in big endian.
+    ///
+    /// This works like [`Self::prf_below_le`], except that the modulus and
+    /// the output are in big endian.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the modulus is zero, or if the lengths don't match.
+    pub fn prf_below_be(&mut self, modulus: &[u8], out: &mut [u8]) {
+        assert_eq!(
+            modulus.len(),
+            out.len(),
+            "modulus and output lengths differ"
+        );
+        let (len, mask) = sample_shape(modulus.iter().rev());
+        out.fill(0);
+        if len == 0 {
+            return;
+        }
+        let start = out.len() - len;
+        let mut more = false;
+        loop {
+            self.prf(&mut out[start..], more);
+            more = true;
+            out[start] &= mask;
+            if less_than_le(out.iter().rev(), modulus.iter().rev()) {
+                return;
+            }
+        }
+    }
+
+    /// Squeeze out a uniformly random integer below a bound.
+    ///
+    /// This is the same as [`Self::prf_below_le`], with the bound encoded
+    /// as 8 bytes.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the bound is zero.
+    pub fn prf_u64_below(&mut self, bound: u64) -> u64 {
+        let mut out = [0u8; 8];
+        self.prf_below_le(&bound.to_le_bytes(), &mut out);
+        u64::from_le_bytes(out)
+    }
+
+    /// Squeeze out a uniformly random integer in a range.
+    ///
+    /// ```
+    /// use ck_meow::Meow;
+    ///
+    /// let mut meow = Meow::new(b"my protocol");
+    /// let roll = meow.prf_range(1..7);
+    /// assert!((1..7).contains(&roll));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This panics if the range is empty.
+    pub fn prf_range(&mut self, range: Range<u64>) -> u64 {
+        assert!(!range.is_empty(), "range must not be empty");
+        range.start + self.prf_u64_below(range.end - range.start)
+    }
+
+    /// Squeeze out uniformly random bits.
+    ///
+    /// This is a single `prf` operation, squeezing out one byte for every
+    /// 8 bits, with the bits of each byte taken from least to most significant.
+    pub fn prf_bits(&mut self, out: &mut [bool]) {
+        let mut more = false;
+        for chunk in out.chunks_mut(8) {
+            let mut byte = [0u8; 1];
+            self.prf(&mut byte, more);
+            more = true;
+            for (i, bit) in chunk.iter_mut().enumerate() {
+                *bit = (byte[0] >> i) & 1 == 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_less_than_le() {
+        let cases: [(&[u8], &[u8], bool); 6] = [
+            (&[0], &[1], true),
+            (&[1], &[1], false),
+            (&[2], &[1], false),
+            (&[0xFF, 0], &[0, 1], true),
+            (&[0, 1], &[0xFF, 0], false),
+            (&[1, 1], &[0, 1], false),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(less_than_le(a.iter(), b.iter()), expected);
+        }
+    }
+
+    #[test]
+    fn test_below_power_of_two_is_prf() {
+        let mut meow0 = Meow::new(b"sample test");
+        let mut meow1 = meow0.clone();
+        let mut out = [0xAA; 4];
+        meow0.prf_below_le(&[0, 0, 1, 0], &mut out);
+        let mut expected = [0u8; 4];
+        meow1.prf(&mut expected[..2], false);
+        assert_eq!(out, expected);
+
+        meow0.prf_below_be(&[0, 0, 0x10], &mut out[..3]);
+        meow1.prf(&mut expected[..1], false);
+        expected[0] &= 0x0F;
+        assert_eq!(out[..3], [0, 0, expected[0]]);
+
+        meow0.prf_below_le(&[1, 0], &mut out[..2]);
+        assert_eq!(out[..2], [0, 0]);
+        // A modulus of 1 doesn't squeeze anything.
+        let mut bytes = [0u8; 4];
+        meow0.prf(&mut bytes, false);
+        meow1.prf(&mut expected, false);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_below_is_in_range() {
+        let mut meow = Meow::new(b"sample test");
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let mut out = [0xFF; 2];
+            meow.prf_below_le(&[3, 0], &mut out);
+            assert_eq!(out[1], 0);
+            seen[out[0] as usize] = true;
+
+            meow.prf_below_be(&[0, 3], &mut out);
+            assert_eq!(out[0], 0);
+            assert!(out[1] < 3);
+
+            let modulus = [0x01, 0x00, 0x01];
+            let mut out = [0u8; 3];
+            meow.prf_below_be(&modulus, &mut out);
+            assert!(out <= [0x01, 0x00, 0x00]);
+        }
+        assert_eq!(seen, [true; 3]);
+    }
+
+    #[test]
+    fn test_parties_agree() {
+        let mut meow0 = Meow::new(b"sample test");
+        let mut meow1 = meow0.clone();
+        let modulus = [0xED, 0xD3, 0xF5, 0x5C, 0x1A, 0x63, 0x12, 0x58, 0x01];
+        for _ in 0..20 {
+            let mut out0 = [0u8; 9];
+            let mut out1 = [0u8; 9];
+            meow0.prf_below_le(&modulus, &mut out0);
+            meow1.prf_below_le(&modulus, &mut out1);
+            assert_eq!(out0, out1);
+        }
+        assert_eq!(meow0.prf_range(10..20), meow1.prf_range(10..20));
+    }
+
+    #[test]
+    fn test_u64_and_range() {
+        let mut meow = Meow::new(b"sample test");
+        for _ in 0..100 {
+            assert_eq!(meow.prf_u64_below(1), 0);
+            assert!(meow.prf_u64_below(1000) < 1000);
+            assert!((u64::MAX - 5..u64::MAX).contains(&meow.prf_range(u64::MAX - 5..u64::MAX)));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_modulus_panics() {
+        Meow::new(b"sample test").prf_below_le(&[0, 0], &mut [0, 0]);
+    }
+
+    #[test]
+    fn test_bits_match_prf() {
+        let mut meow0 = Meow::new(b"sample test");
+        let mut meow1 = meow0.clone();
+        let mut bits = [false; 12];
+        meow0.prf_bits(&mut bits);
+        let mut bytes = [0u8; 2];
+        meow1.prf(&mut bytes, false);
+        for (i, bit) in bits.iter().enumerate() {
+            assert_eq!(*bit, (bytes[i / 8] >> (i % 8)) & 1 == 1);
+        }
+    }
+}
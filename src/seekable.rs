@@ -0,0 +1,669 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+#[cfg(feature = "std")]
+use zeroize::Zeroizing;
+
+use crate::meow::Meow;
+
+/// The label absorbed by every state derived for a seekable file.
+const SEEKABLE_LABEL: &[u8] = b"ck-meow seekable";
+/// The version of the format, which is the first byte of the header.
+const SEEKABLE_VERSION: u8 = 1;
+/// The number of bytes in the identifier of a file.
+pub const FILE_ID_LEN: usize = 16;
+/// The number of bytes in the MAC of the header, and of each chunk.
+pub const SEEKABLE_MAC_LEN: usize = 16;
+/// The number of bytes in the fields of the header, before its MAC.
+const HEADER_FIELDS_LEN: usize = 1 + FILE_ID_LEN + 4 + 8;
+/// The number of bytes in the header of a seekable file.
+///
+/// The format is:
+///
+/// - 1 byte: the version of the format, currently 1.
+/// - 16 bytes: the identifier of the file.
+/// - 4 bytes: the number of bytes of plaintext in each chunk, in little endian.
+/// - 8 bytes: the total number of bytes of plaintext, in little endian.
+/// - 16 bytes: a MAC of the previous fields.
+pub const SEEKABLE_HEADER_LEN: usize = HEADER_FIELDS_LEN + SEEKABLE_MAC_LEN;
+
+/// An error produced when reading a seekable file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SeekableError {
+    /// The header uses an unknown version of the format, or an invalid chunk size.
+    Format,
+    /// The header failed to authenticate, because of the wrong key, or tampering.
+    Header,
+    /// A chunk doesn't exist, or has the wrong length.
+    Chunk,
+    /// A chunk failed to authenticate, because of the wrong key, or tampering.
+    Mac,
+}
+
+impl fmt::Display for SeekableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeekableError::Format => write!(f, "Seekable file has an invalid format."),
+            SeekableError::Header => write!(f, "Seekable file header failed to authenticate."),
+            SeekableError::Chunk => write!(f, "Chunk has the wrong index or length."),
+            SeekableError::Mac => write!(f, "Chunk failed to authenticate."),
+        }
+    }
+}
+
+impl core::error::Error for SeekableError {}
+
+#[cfg(feature = "std")]
+impl From<SeekableError> for io::Error {
+    fn from(error: SeekableError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// The keys for a file split into independently encrypted chunks.
+///
+/// A file consists of a header, followed by chunks of a fixed size, with only
+/// the last chunk being shorter. Each chunk is encrypted with its own state,
+/// derived from a keyed base state, so that any chunk can be decrypted and
+/// authenticated without processing the rest of the file:
+///
+/// ```
+/// use ck_meow::Meow;
+///
+/// # let mut base = Meow::new(b"my protocol");
+/// # let (file_id, chunk_size, index) = ([0u8; 16], 4096u32, 7u64);
+/// # let mut chunk = [0u8; 4096];
+/// base.meta_ad(b"ck-meow seekable", false);
+/// // For each chunk:
+/// let mut meow = base.clone();
+/// meow.meta_ad(b"chunk", false);
+/// meow.meta_ad(&file_id, false);
+/// meow.meta_ad(&chunk_size.to_le_bytes(), false);
+/// meow.meta_ad(&index.to_le_bytes(), false);
+/// meow.send_enc(&mut chunk, false);
+/// let mut mac = [0u8; 16];
+/// meow.send_mac(&mut mac);
+/// ```
+///
+/// The header is authenticated in the same way, with the label `header`,
+/// sending its fields with `send_clr`, followed by `send_mac`. Since it contains
+/// the total length, a reader knows how many chunks to expect, and how long
+/// they should be.
+///
+/// Chunks are bound to the identifier of the file, but not to its length,
+/// so that files can be written without knowing their length in advance.
+/// Each file encrypted with the same key needs a unique identifier, for example
+/// 16 random bytes, otherwise chunks from different files could be swapped.
+#[derive(Clone)]
+pub struct SeekableFile {
+    base: Meow,
+    file_id: [u8; FILE_ID_LEN],
+    chunk_size: u32,
+}
+
+impl SeekableFile {
+    /// Create the keys for a new file, given a keyed state, a unique identifier, and a chunk size.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the chunk size is zero.
+    pub fn new(mut meow: Meow, file_id: [u8; FILE_ID_LEN], chunk_size: u32) -> Self {
+        assert!(chunk_size > 0, "chunk size must not be zero");
+        meow.meta_ad(SEEKABLE_LABEL, false);
+        Self {
+            base: meow,
+            file_id,
+            chunk_size,
+        }
+    }
+
+    /// Open an existing file, given a keyed state and its header.
+    ///
+    /// This returns the keys for the file, along with its total length,
+    /// after authenticating the header.
+    pub fn open(
+        meow: Meow,
+        header: &[u8; SEEKABLE_HEADER_LEN],
+    ) -> Result<(Self, u64), SeekableError> {
+        let (fields, mac) = header.split_at(HEADER_FIELDS_LEN);
+        let (version, rest) = fields.split_at(1);
+        let (file_id, rest) = rest.split_at(FILE_ID_LEN);
+        let (chunk_size, total_len) = rest.split_at(4);
+        let chunk_size = u32::from_le_bytes(chunk_size.try_into().expect("4 bytes"));
+        let total_len = u64::from_le_bytes(total_len.try_into().expect("8 bytes"));
+        if version != [SEEKABLE_VERSION] || chunk_size == 0 {
+            return Err(SeekableError::Format);
+        }
+
+        let file = Self::new(meow, file_id.try_into().expect("16 bytes"), chunk_size);
+        let mut meow = file.base.clone();
+        meow.meta_ad(b"header", false);
+        meow.recv_clr(fields, false);
+        let mut mac: [u8; SEEKABLE_MAC_LEN] = mac.try_into().expect("16 bytes");
+        meow.recv_mac(&mut mac).map_err(|_| SeekableError::Header)?;
+        Ok((file, total_len))
+    }
+
+    /// Create the header for this file, given its total length.
+    pub fn seal_header(&self, total_len: u64) -> [u8; SEEKABLE_HEADER_LEN] {
+        let mut header = [0u8; SEEKABLE_HEADER_LEN];
+        let (fields, mac) = header.split_at_mut(HEADER_FIELDS_LEN);
+        fields[0] = SEEKABLE_VERSION;
+        fields[1..1 + FILE_ID_LEN].copy_from_slice(&self.file_id);
+        fields[1 + FILE_ID_LEN..5 + FILE_ID_LEN].copy_from_slice(&self.chunk_size.to_le_bytes());
+        fields[5 + FILE_ID_LEN..].copy_from_slice(&total_len.to_le_bytes());
+
+        let mut meow = self.base.clone();
+        meow.meta_ad(b"header", false);
+        meow.send_clr(fields, false);
+        meow.send_mac(mac);
+        header
+    }
+
+    /// The identifier of this file.
+    pub fn file_id(&self) -> &[u8; FILE_ID_LEN] {
+        &self.file_id
+    }
+
+    /// The number of bytes of plaintext in each chunk, except possibly the last.
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    /// The number of chunks in a file of a given length.
+    pub fn chunk_count(&self, total_len: u64) -> u64 {
+        total_len.div_ceil(u64::from(self.chunk_size))
+    }
+
+    /// The number of bytes of plaintext in a given chunk, or `None` if it doesn't exist.
+    pub fn chunk_len(&self, total_len: u64, index: u64) -> Option<usize> {
+        let start = index.checked_mul(u64::from(self.chunk_size))?;
+        let len = total_len
+            .checked_sub(start)?
+            .min(u64::from(self.chunk_size));
+        (len > 0).then_some(len as usize)
+    }
+
+    /// The offset of a chunk in the file, counting the header, or `None` if it overflows.
+    ///
+    /// Each chunk takes [`SEEKABLE_MAC_LEN`] more bytes than its plaintext.
+    pub fn chunk_offset(&self, index: u64) -> Option<u64> {
+        let encrypted_size = u64::from(self.chunk_size) + SEEKABLE_MAC_LEN as u64;
+        index
+            .checked_mul(encrypted_size)?
+            .checked_add(SEEKABLE_HEADER_LEN as u64)
+    }
+
+    /// Derive the state used for a given chunk.
+    fn chunk_state(&self, index: u64) -> Meow {
+        let mut meow = self.base.clone();
+        meow.meta_ad(b"chunk", false);
+        meow.meta_ad(&self.file_id, false);
+        meow.meta_ad(&self.chunk_size.to_le_bytes(), false);
+        meow.meta_ad(&index.to_le_bytes(), false);
+        meow
+    }
+
+    /// Encrypt a chunk in place, returning its MAC.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the chunk is empty, or larger than the chunk size.
+    pub fn seal_chunk(&self, index: u64, chunk: &mut [u8]) -> [u8; SEEKABLE_MAC_LEN] {
+        assert!(
+            !chunk.is_empty() && chunk.len() <= self.chunk_size as usize,
+            "chunk has an invalid length"
+        );
+        let mut meow = self.chunk_state(index);
+        meow.send_enc(chunk, false);
+        let mut mac = [0u8; SEEKABLE_MAC_LEN];
+        meow.send_mac(&mut mac);
+        mac
+    }
+
+    /// Decrypt and authenticate a chunk in place.
+    ///
+    /// The total length is used to check that the chunk has the right length.
+    /// If the chunk fails to authenticate, it gets zeroed.
+    pub fn open_chunk(
+        &self,
+        total_len: u64,
+        index: u64,
+        chunk: &mut [u8],
+        mac: &[u8; SEEKABLE_MAC_LEN],
+    ) -> Result<(), SeekableError> {
+        if self.chunk_len(total_len, index) != Some(chunk.len()) {
+            return Err(SeekableError::Chunk);
+        }
+        let mut meow = self.chunk_state(index);
+        let mut mac = *mac;
+        meow.recv_enc_and_mac(chunk, &mut mac)
+            .map_err(|_| SeekableError::Mac)
+    }
+}
+
+/// A writer for a seekable file.
+///
+/// The header is written last, once the total length is known, which is why
+/// the underlying writer needs to be seekable. The file needs to be completed
+/// with [`Self::finish`]. If a chunk fails to be written to the underlying
+/// writer, every write after that fails as well.
+///
+/// ```
+/// use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+///
+/// use ck_meow::{Meow, SeekableFile, SeekableReader, SeekableWriter};
+///
+/// let mut meow = Meow::new(b"my protocol");
+/// meow.key(b"a shared secret", false);
+///
+/// let file = SeekableFile::new(meow.clone(), [1u8; 16], 4096);
+/// let mut writer = SeekableWriter::new(file, Cursor::new(Vec::new())).unwrap();
+/// writer.write_all(&[0xAA; 10000]).unwrap();
+/// let encrypted = writer.finish().unwrap().into_inner();
+///
+/// let mut reader = SeekableReader::new(meow, Cursor::new(encrypted)).unwrap();
+/// assert_eq!(reader.len(), 10000);
+/// reader.seek(SeekFrom::Start(9000)).unwrap();
+/// let mut data = Vec::new();
+/// reader.read_to_end(&mut data).unwrap();
+/// assert_eq!(data, [0xAA; 1000]);
+/// ```
+#[cfg(feature = "std")]
+pub struct SeekableWriter<W: Write + Seek> {
+    file: SeekableFile,
+    inner: W,
+    /// The offset of the header in the underlying writer.
+    start: u64,
+    /// The plaintext of the current chunk, with room for the MAC.
+    buffer: Zeroizing<Vec<u8>>,
+    index: u64,
+    total_len: u64,
+    poisoned: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Seek> SeekableWriter<W> {
+    /// Create a new writer, starting at the current position of the underlying writer.
+    ///
+    /// This reserves space for the header, which gets written at the end.
+    pub fn new(file: SeekableFile, mut inner: W) -> io::Result<Self> {
+        let start = inner.stream_position()?;
+        inner.write_all(&[0u8; SEEKABLE_HEADER_LEN])?;
+        let capacity = file.chunk_size as usize + SEEKABLE_MAC_LEN;
+        Ok(Self {
+            file,
+            inner,
+            start,
+            buffer: Zeroizing::new(Vec::with_capacity(capacity)),
+            index: 0,
+            total_len: 0,
+            poisoned: false,
+        })
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other(
+                "seekable writer was poisoned by a failed write",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encrypt the buffered chunk, and write it out.
+    fn write_chunk(&mut self) -> io::Result<()> {
+        self.check()?;
+        let mac = self.file.seal_chunk(self.index, &mut self.buffer);
+        self.buffer.extend_from_slice(&mac);
+        self.index += 1;
+        let result = self.inner.write_all(&self.buffer);
+        self.buffer.clear();
+        // The chunk has already been sealed, so it can't be written again.
+        self.poisoned = result.is_err();
+        result
+    }
+
+    /// Write out the last chunk, and the header, returning the underlying writer.
+    ///
+    /// The underlying writer is left positioned at the end of the file.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.check()?;
+        if !self.buffer.is_empty() {
+            self.write_chunk()?;
+        }
+        let end = self.inner.stream_position()?;
+        self.inner.seek(SeekFrom::Start(self.start))?;
+        self.inner
+            .write_all(&self.file.seal_header(self.total_len))?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Seek> Write for SeekableWriter<W> {
+    /// Buffer data for the current chunk, returning how much was accepted.
+    ///
+    /// This fills at most one chunk per call, so that an error writing out a
+    /// chunk never happens after part of the data has been accepted.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        let chunk_size = self.file.chunk_size as usize;
+        if self.buffer.len() == chunk_size {
+            self.write_chunk()?;
+        }
+        let take = buf.len().min(chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..take]);
+        self.total_len += take as u64;
+        Ok(take)
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// This doesn't write out the current chunk, since only whole chunks can be written.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader for a seekable file, decrypting only the chunks which are read.
+///
+/// Each chunk is authenticated before any of its data is returned.
+#[cfg(feature = "std")]
+pub struct SeekableReader<R: Read + Seek> {
+    file: SeekableFile,
+    inner: R,
+    /// The offset of the header in the underlying reader.
+    start: u64,
+    total_len: u64,
+    /// The position in the plaintext.
+    pos: u64,
+    /// The index of the decrypted chunk in the buffer, if any.
+    cached: Option<u64>,
+    buffer: Zeroizing<Vec<u8>>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> SeekableReader<R> {
+    /// Open a file, starting at the current position of the underlying reader.
+    ///
+    /// This reads and authenticates the header.
+    pub fn new(meow: Meow, mut inner: R) -> io::Result<Self> {
+        let start = inner.stream_position()?;
+        let mut header = [0u8; SEEKABLE_HEADER_LEN];
+        inner.read_exact(&mut header)?;
+        let (file, total_len) = SeekableFile::open(meow, &header)?;
+        let capacity = file.chunk_size as usize + SEEKABLE_MAC_LEN;
+        Ok(Self {
+            file,
+            inner,
+            start,
+            total_len,
+            pos: 0,
+            cached: None,
+            buffer: Zeroizing::new(Vec::with_capacity(capacity)),
+        })
+    }
+
+    /// The total number of bytes of plaintext in the file.
+    pub fn len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Whether or not the file contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.total_len == 0
+    }
+
+    /// Read, decrypt, and authenticate a chunk, unless it's already in the buffer.
+    fn load_chunk(&mut self, index: u64) -> io::Result<()> {
+        if self.cached == Some(index) {
+            return Ok(());
+        }
+        self.cached = None;
+        let len = self
+            .file
+            .chunk_len(self.total_len, index)
+            .ok_or(SeekableError::Chunk)?;
+        let offset = self
+            .file
+            .chunk_offset(index)
+            .and_then(|offset| offset.checked_add(self.start))
+            .ok_or(SeekableError::Chunk)?;
+        self.inner.seek(SeekFrom::Start(offset))?;
+        self.buffer.resize(len + SEEKABLE_MAC_LEN, 0);
+        self.inner.read_exact(&mut self.buffer)?;
+        let (chunk, mac) = self.buffer.split_at_mut(len);
+        let mac = (&*mac).try_into().expect("MAC has the right length");
+        self.file.open_chunk(self.total_len, index, chunk, mac)?;
+        self.buffer.truncate(len);
+        self.cached = Some(index);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Read for SeekableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.total_len || buf.is_empty() {
+            return Ok(0);
+        }
+        let chunk_size = u64::from(self.file.chunk_size);
+        self.load_chunk(self.pos / chunk_size)?;
+        let offset = (self.pos % chunk_size) as usize;
+        let take = buf.len().min(self.buffer.len() - offset);
+        buf[..take].copy_from_slice(&self.buffer[offset..offset + take]);
+        self.pos += take as u64;
+        Ok(take)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Seek for SeekableReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.total_len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        let Some(new_pos) = new_pos else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        };
+        self.pos = new_pos;
+        Ok(new_pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{keyed, seal_by_hand};
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let file = SeekableFile::new(keyed(), [1u8; 16], 8);
+        let mut chunk = *b"abcdefgh";
+        let mac = file.seal_chunk(3, &mut chunk);
+
+        let mut meow = keyed();
+        meow.meta_ad(SEEKABLE_LABEL, false);
+        meow.meta_ad(b"chunk", false);
+        meow.meta_ad(&[1u8; 16], false);
+        meow.meta_ad(&8u32.to_le_bytes(), false);
+        meow.meta_ad(&3u64.to_le_bytes(), false);
+        let (expected, expected_mac) = seal_by_hand(&mut meow, b"abcdefgh");
+
+        assert_eq!(chunk[..], expected[..]);
+        assert_eq!(mac, expected_mac);
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let file = SeekableFile::new(keyed(), [1u8; 16], 8);
+        let header = file.seal_header(100);
+        let (opened, total_len) = SeekableFile::open(keyed(), &header).unwrap();
+        assert_eq!(total_len, 100);
+        assert_eq!(opened.file_id(), &[1u8; 16]);
+        assert_eq!(opened.chunk_size(), 8);
+        assert_eq!(opened.chunk_count(total_len), 13);
+        assert_eq!(opened.chunk_len(total_len, 12), Some(4));
+        assert_eq!(opened.chunk_len(total_len, 13), None);
+        assert_eq!(
+            opened.chunk_offset(1),
+            Some((SEEKABLE_HEADER_LEN + 8 + SEEKABLE_MAC_LEN) as u64)
+        );
+        assert_eq!(opened.chunk_offset(u64::MAX), None);
+
+        for i in 1..SEEKABLE_HEADER_LEN {
+            let mut bad = header;
+            bad[i] ^= 1;
+            assert!(SeekableFile::open(keyed(), &bad).is_err());
+        }
+        let mut bad = header;
+        bad[0] = 2;
+        assert!(matches!(
+            SeekableFile::open(keyed(), &bad),
+            Err(SeekableError::Format)
+        ));
+        let mut other = Meow::new(b"seekable test");
+        other.key(b"another secret", false);
+        assert!(matches!(
+            SeekableFile::open(other, &header),
+            Err(SeekableError::Header)
+        ));
+    }
+
+    #[test]
+    fn test_chunks_are_independent() {
+        let file = SeekableFile::new(keyed(), [1u8; 16], 8);
+        let other_file = SeekableFile::new(keyed(), [2u8; 16], 8);
+        let mut chunks = [[0u8; 8]; 3];
+        let macs: Vec<_> = chunks
+            .iter_mut()
+            .enumerate()
+            .map(|(i, chunk)| {
+                chunk.fill(i as u8);
+                file.seal_chunk(i as u64, chunk)
+            })
+            .collect();
+
+        // Chunks can be opened in any order.
+        for i in [2, 0, 1] {
+            let mut chunk = chunks[i];
+            file.open_chunk(24, i as u64, &mut chunk, &macs[i]).unwrap();
+            assert_eq!(chunk, [i as u8; 8]);
+        }
+
+        let mut chunk = chunks[1];
+        assert_eq!(
+            file.open_chunk(24, 0, &mut chunk, &macs[1]),
+            Err(SeekableError::Mac)
+        );
+        assert_eq!(chunk, [0u8; 8]);
+        let mut chunk = chunks[1];
+        assert_eq!(
+            other_file.open_chunk(24, 1, &mut chunk, &macs[1]),
+            Err(SeekableError::Mac)
+        );
+        let mut chunk = chunks[2];
+        assert_eq!(
+            file.open_chunk(20, 2, &mut chunk, &macs[2]),
+            Err(SeekableError::Chunk)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    fn encrypt(data: &[u8], chunk_size: u32) -> Vec<u8> {
+        let file = SeekableFile::new(keyed(), [7u8; 16], chunk_size);
+        let mut writer = SeekableWriter::new(file, std::io::Cursor::new(Vec::new())).unwrap();
+        for piece in data.chunks(5) {
+            writer.write_all(piece).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_random_access() {
+        let data: Vec<u8> = (0..100).collect();
+        for chunk_size in [1, 7, 10, 100, 1000] {
+            for len in [0, 1, 10, 99, 100] {
+                let encrypted = encrypt(&data[..len], chunk_size);
+                let chunks = len.div_ceil(chunk_size as usize);
+                assert_eq!(
+                    encrypted.len(),
+                    SEEKABLE_HEADER_LEN + len + chunks * SEEKABLE_MAC_LEN
+                );
+                let mut reader =
+                    SeekableReader::new(keyed(), std::io::Cursor::new(encrypted)).unwrap();
+                assert_eq!(reader.len(), len as u64);
+                for start in [len / 2, 0, len.saturating_sub(3), len] {
+                    reader.seek(SeekFrom::Start(start as u64)).unwrap();
+                    let mut out = Vec::new();
+                    reader.read_to_end(&mut out).unwrap();
+                    assert_eq!(out, &data[start..len]);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_tampering_only_affects_its_chunk() {
+        let data = [0xAA; 100];
+        let mut encrypted = encrypt(&data, 10);
+        let second_chunk = SEEKABLE_HEADER_LEN + 10 + SEEKABLE_MAC_LEN;
+        encrypted[second_chunk + 3] ^= 1;
+        let mut reader = SeekableReader::new(keyed(), std::io::Cursor::new(encrypted)).unwrap();
+        let mut out = [0u8; 10];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0xAA; 10]);
+        assert_eq!(
+            reader.read(&mut out).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        reader.seek(SeekFrom::Start(50)).unwrap();
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0xAA; 10]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_truncation_is_detected() {
+        let encrypted = encrypt(&[0xAA; 100], 10);
+        let truncated = encrypted[..encrypted.len() - 1].to_vec();
+        let mut reader = SeekableReader::new(keyed(), std::io::Cursor::new(truncated)).unwrap();
+        reader.seek(SeekFrom::End(-5)).unwrap();
+        let mut out = Vec::new();
+        assert!(reader.read_to_end(&mut out).is_err());
+        assert!(out.is_empty());
+
+        assert!(SeekableReader::new(keyed(), std::io::Cursor::new(&encrypted[..10])).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_failed_writes_poison_the_writer() {
+        // There's only room for the header, and part of a chunk.
+        let mut out = [0u8; SEEKABLE_HEADER_LEN + 4];
+        let file = SeekableFile::new(keyed(), [7u8; 16], 8);
+        let mut writer = SeekableWriter::new(file, std::io::Cursor::new(&mut out[..])).unwrap();
+        // The first chunk only gets written once more data arrives.
+        assert_eq!(writer.write(b"0123456789").unwrap(), 8);
+        assert_eq!(writer.total_len, 8);
+        assert_eq!(
+            writer.write(b"89").unwrap_err().kind(),
+            io::ErrorKind::WriteZero
+        );
+        assert!(writer.write(b"89").is_err());
+        assert_eq!(writer.total_len, 8);
+        assert!(writer.finish().is_err());
+    }
+}
@@ -0,0 +1,444 @@
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use crate::meow::Meow;
+
+/// The number of bytes in the header of a record, holding the length of the message.
+pub const RECORD_HEADER_LEN: usize = 4;
+/// The number of bytes in the MAC ending each record.
+pub const RECORD_MAC_LEN: usize = 16;
+/// The number of bytes a record adds to a message.
+pub const RECORD_OVERHEAD: usize = RECORD_HEADER_LEN + RECORD_MAC_LEN;
+
+/// The default maximum number of bytes in a message, as in TLS.
+const DEFAULT_MAX_RECORD_SIZE: usize = 1 << 14;
+/// By default, ratchet after this many records.
+const DEFAULT_RATCHET_RECORDS: u64 = 1 << 10;
+/// By default, ratchet after this many bytes of messages.
+const DEFAULT_RATCHET_BYTES: u64 = 1 << 20;
+
+/// An error produced when sealing or opening a record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionError {
+    /// A previous record failed to authenticate, so the session can't be used anymore.
+    Poisoned,
+    /// The message is larger than the maximum record size.
+    TooLarge,
+    /// A buffer is too small, or a record has the wrong length.
+    Length,
+    /// The record failed to authenticate, because of tampering, or being out of order.
+    Mac,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Poisoned => write!(f, "Session was poisoned by a failed record."),
+            SessionError::TooLarge => write!(f, "Record exceeds the maximum size."),
+            SessionError::Length => write!(f, "Record has the wrong length."),
+            SessionError::Mac => write!(f, "Record failed to authenticate."),
+        }
+    }
+}
+
+impl core::error::Error for SessionError {}
+
+#[cfg(feature = "std")]
+impl From<SessionError> for std::io::Error {
+    fn from(error: SessionError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// A session exchanging records over a keyed Meow instance.
+///
+/// Each record is the length of the message, the encrypted message, and a MAC.
+/// Sealing a message does the following operations:
+///
+/// ```
+/// use ck_meow::Meow;
+///
+/// # let mut meow = Meow::new(b"my protocol");
+/// # meow.key(b"a shared secret", false);
+/// # let (sequence, mut message) = (0u64, *b"hello");
+/// meow.meta_ad(&sequence.to_le_bytes(), false);
+/// meow.meta_send_clr(&(message.len() as u32).to_le_bytes(), false);
+/// meow.send_enc(&mut message, false);
+/// let mut mac = [0u8; 16];
+/// meow.send_mac(&mut mac);
+/// ```
+///
+/// and opening a record does the same, with the receiving operations instead.
+/// The sequence number counts the records exchanged so far, and isn't sent.
+/// After a given number of records, or bytes, the state gets ratcheted,
+/// so that compromising it doesn't reveal earlier messages.
+///
+/// Because there's a single state, records need to be opened in the same
+/// order they were sealed, even when both parties send records. To send
+/// records in both directions at the same time, see [`Meow::split`].
+///
+/// If a record fails to authenticate, the session is poisoned, and every
+/// further operation fails.
+///
+/// ```
+/// use ck_meow::{Meow, Session};
+///
+/// let mut meow = Meow::new(b"my protocol");
+/// meow.key(b"a shared secret", false);
+/// let mut alice = Session::new(meow.clone());
+/// let mut bob = Session::new(meow);
+///
+/// let mut record = [0u8; 64];
+/// let len = alice.seal_into(b"hello", &mut record).unwrap();
+/// let mut message = [0u8; 64];
+/// let message_len = bob.open_into(&record[..len], &mut message).unwrap();
+/// assert_eq!(&message[..message_len], b"hello");
+/// ```
+#[derive(Clone)]
+pub struct Session {
+    meow: Meow,
+    max_record_size: usize,
+    ratchet_records: u64,
+    ratchet_bytes: u64,
+    /// The number of records exchanged so far.
+    sequence: u64,
+    /// The number of records exchanged since the last ratchet.
+    records: u64,
+    /// The number of bytes of messages exchanged since the last ratchet.
+    bytes: u64,
+    poisoned: bool,
+}
+
+impl Session {
+    /// Create a new session, from a state which has already absorbed a key.
+    ///
+    /// By default, messages can be at most 16 KiB, and the state gets ratcheted
+    /// every 1024 records, or every MiB of messages, whichever comes first.
+    pub fn new(meow: Meow) -> Self {
+        Self {
+            meow,
+            max_record_size: DEFAULT_MAX_RECORD_SIZE,
+            ratchet_records: DEFAULT_RATCHET_RECORDS,
+            ratchet_bytes: DEFAULT_RATCHET_BYTES,
+            sequence: 0,
+            records: 0,
+            bytes: 0,
+            poisoned: false,
+        }
+    }
+
+    /// Set the maximum number of bytes in a message.
+    ///
+    /// Both parties need to use the same limits.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the size doesn't fit in the 4 byte header of a record.
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        assert!(
+            u32::try_from(max_record_size).is_ok(),
+            "maximum record size must fit in 32 bits"
+        );
+        self.max_record_size = max_record_size;
+        self
+    }
+
+    /// Set how many records, or bytes of messages, to exchange between ratchets.
+    ///
+    /// Both parties need to use the same limits.
+    ///
+    /// # Panics
+    ///
+    /// This panics if either limit is zero.
+    pub fn with_ratchet_interval(mut self, records: u64, bytes: u64) -> Self {
+        assert!(
+            records > 0 && bytes > 0,
+            "ratchet interval must not be zero"
+        );
+        self.ratchet_records = records;
+        self.ratchet_bytes = bytes;
+        self
+    }
+
+    /// The maximum number of bytes in a message.
+    pub fn max_record_size(&self) -> usize {
+        self.max_record_size
+    }
+
+    /// The number of records exchanged so far, in either direction.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Whether or not a record has failed to authenticate, making the session unusable.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// The total length of a record, given its header.
+    ///
+    /// This is useful for reading records from a stream. This returns `None`
+    /// if the length doesn't fit in a `usize`, which can happen on 32-bit targets.
+    pub fn record_len(header: [u8; RECORD_HEADER_LEN]) -> Option<usize> {
+        usize::try_from(u32::from_le_bytes(header))
+            .ok()?
+            .checked_add(RECORD_OVERHEAD)
+    }
+
+    fn check(&self, len: usize) -> Result<(), SessionError> {
+        if self.poisoned {
+            return Err(SessionError::Poisoned);
+        }
+        if len > self.max_record_size {
+            return Err(SessionError::TooLarge);
+        }
+        Ok(())
+    }
+
+    /// Advance the sequence number, ratcheting if we've reached the interval.
+    fn advance(&mut self, len: usize) {
+        self.sequence += 1;
+        self.records += 1;
+        self.bytes += len as u64;
+        if self.records >= self.ratchet_records || self.bytes >= self.ratchet_bytes {
+            self.meow.ratchet();
+            self.records = 0;
+            self.bytes = 0;
+        }
+    }
+
+    /// Seal a message into a record, returning the length of the record.
+    ///
+    /// The record takes [`RECORD_OVERHEAD`] more bytes than the message.
+    pub fn seal_into(&mut self, message: &[u8], record: &mut [u8]) -> Result<usize, SessionError> {
+        self.check(message.len())?;
+        let len = RECORD_OVERHEAD + message.len();
+        if record.len() < len {
+            return Err(SessionError::Length);
+        }
+        let (header, rest) = record[..len].split_at_mut(RECORD_HEADER_LEN);
+        let (body, mac) = rest.split_at_mut(message.len());
+
+        self.meow.meta_ad(&self.sequence.to_le_bytes(), false);
+        header.copy_from_slice(&(message.len() as u32).to_le_bytes());
+        self.meow.meta_send_clr(header, false);
+        body.copy_from_slice(message);
+        self.meow.send_enc(body, false);
+        self.meow.send_mac(mac);
+
+        self.advance(message.len());
+        Ok(len)
+    }
+
+    /// Open a record, writing out the message, and returning its length.
+    ///
+    /// The record needs to be complete, with exactly the length given in its header.
+    /// If the record fails to authenticate, the session gets poisoned.
+    pub fn open_into(&mut self, record: &[u8], message: &mut [u8]) -> Result<usize, SessionError> {
+        if self.poisoned {
+            return Err(SessionError::Poisoned);
+        }
+        let header: [u8; RECORD_HEADER_LEN] = record
+            .get(..RECORD_HEADER_LEN)
+            .and_then(|header| header.try_into().ok())
+            .ok_or(SessionError::Length)?;
+        let len = u32::from_le_bytes(header) as usize;
+        self.check(len)?;
+        if Self::record_len(header) != Some(record.len()) || message.len() < len {
+            return Err(SessionError::Length);
+        }
+        let body = &mut message[..len];
+        body.copy_from_slice(&record[RECORD_HEADER_LEN..RECORD_HEADER_LEN + len]);
+        let mut mac = [0u8; RECORD_MAC_LEN];
+        mac.copy_from_slice(&record[RECORD_HEADER_LEN + len..]);
+
+        self.meow.meta_ad(&self.sequence.to_le_bytes(), false);
+        self.meow.meta_recv_clr(&header, false);
+        if self.meow.recv_enc_and_mac(body, &mut mac).is_err() {
+            self.poisoned = true;
+            return Err(SessionError::Mac);
+        }
+
+        self.advance(len);
+        Ok(len)
+    }
+
+    /// Seal a message into a record.
+    #[cfg(feature = "alloc")]
+    pub fn seal(&mut self, message: &[u8]) -> Result<Vec<u8>, SessionError> {
+        self.check(message.len())?;
+        let mut record = vec![0u8; RECORD_OVERHEAD + message.len()];
+        self.seal_into(message, &mut record)?;
+        Ok(record)
+    }
+
+    /// Open a record, returning the message.
+    ///
+    /// If the record fails to authenticate, the session gets poisoned.
+    #[cfg(feature = "alloc")]
+    pub fn open(&mut self, record: &[u8]) -> Result<Vec<u8>, SessionError> {
+        let len = record
+            .len()
+            .checked_sub(RECORD_OVERHEAD)
+            .ok_or(SessionError::Length)?;
+        let mut message = vec![0u8; len];
+        self.open_into(record, &mut message)?;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{keyed, seal_by_hand};
+
+    fn pair() -> (Session, Session) {
+        let meow = keyed();
+        (Session::new(meow.clone()), Session::new(meow))
+    }
+
+    fn send(sender: &mut Session, receiver: &mut Session, message: &[u8]) {
+        let mut record = [0u8; 256];
+        let len = sender.seal_into(message, &mut record).unwrap();
+        assert_eq!(len, message.len() + RECORD_OVERHEAD);
+        assert_eq!(
+            Session::record_len(record[..4].try_into().unwrap()),
+            Some(len)
+        );
+        let mut out = [0u8; 256];
+        let out_len = receiver.open_into(&record[..len], &mut out).unwrap();
+        assert_eq!(&out[..out_len], message);
+    }
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let mut meow = keyed();
+        let mut session = Session::new(meow.clone()).with_ratchet_interval(2, 1000);
+        let mut records = [[0u8; 25]; 3];
+        for record in &mut records {
+            session.seal_into(b"hello", record).unwrap();
+        }
+
+        for (sequence, record) in records.iter().enumerate() {
+            meow.meta_ad(&(sequence as u64).to_le_bytes(), false);
+            meow.meta_send_clr(&5u32.to_le_bytes(), false);
+            let (message, mac) = seal_by_hand(&mut meow, b"hello");
+            if sequence == 1 {
+                meow.ratchet();
+            }
+            assert_eq!(record[..4], 5u32.to_le_bytes());
+            assert_eq!(record[4..9], message[..]);
+            assert_eq!(record[9..], mac);
+        }
+    }
+
+    #[test]
+    fn test_both_directions() {
+        let (alice, bob) = pair();
+        let (mut alice, mut bob) = (
+            alice.with_ratchet_interval(3, 20),
+            bob.with_ratchet_interval(3, 20),
+        );
+        for i in 0..10u8 {
+            send(&mut alice, &mut bob, &[i; 7]);
+            send(&mut bob, &mut alice, &[i; 13]);
+            send(&mut bob, &mut alice, b"");
+        }
+        assert_eq!(alice.sequence(), 30);
+        assert_eq!(bob.sequence(), 30);
+    }
+
+    #[test]
+    fn test_mismatched_ratchets_fail() {
+        let (mut alice, bob) = pair();
+        let mut bob = bob.with_ratchet_interval(1, 1000);
+        send(&mut alice, &mut bob, b"first");
+        let mut record = [0u8; 64];
+        let len = alice.seal_into(b"second", &mut record).unwrap();
+        let mut out = [0u8; 64];
+        assert_eq!(
+            bob.open_into(&record[..len], &mut out),
+            Err(SessionError::Mac)
+        );
+    }
+
+    #[test]
+    fn test_failure_poisons() {
+        let (mut alice, mut bob) = pair();
+        let mut record = [0u8; 64];
+        let len = alice.seal_into(b"hello", &mut record).unwrap();
+        let mut bad = record;
+        bad[RECORD_HEADER_LEN] ^= 1;
+        let mut out = [0xAA; 64];
+        assert_eq!(bob.open_into(&bad[..len], &mut out), Err(SessionError::Mac));
+        assert_eq!(out[..5], [0u8; 5]);
+        assert!(bob.is_poisoned());
+        assert_eq!(
+            bob.open_into(&record[..len], &mut out),
+            Err(SessionError::Poisoned)
+        );
+        assert_eq!(
+            bob.seal_into(b"hello", &mut record),
+            Err(SessionError::Poisoned)
+        );
+    }
+
+    #[test]
+    fn test_reordering_fails() {
+        let (mut alice, mut bob) = pair();
+        let mut first = [0u8; 64];
+        let mut second = [0u8; 64];
+        let len = alice.seal_into(b"first", &mut first).unwrap();
+        alice.seal_into(b"first", &mut second).unwrap();
+        let mut out = [0u8; 64];
+        assert_eq!(
+            bob.open_into(&second[..len], &mut out),
+            Err(SessionError::Mac)
+        );
+    }
+
+    #[test]
+    fn test_lengths() {
+        let (alice, bob) = pair();
+        let mut alice = alice.with_max_record_size(8);
+        let mut bob = bob.with_max_record_size(8);
+        let mut record = [0u8; 64];
+        assert_eq!(
+            alice.seal_into(&[0u8; 9], &mut record),
+            Err(SessionError::TooLarge)
+        );
+        assert_eq!(
+            alice.seal_into(&[0u8; 8], &mut record[..27]),
+            Err(SessionError::Length)
+        );
+        let len = alice.seal_into(&[0u8; 8], &mut record).unwrap();
+        let mut out = [0u8; 8];
+        // Bad lengths are rejected without affecting the session.
+        assert_eq!(
+            bob.open_into(&record[..len - 1], &mut out),
+            Err(SessionError::Length)
+        );
+        assert_eq!(
+            bob.open_into(&record[..2], &mut out),
+            Err(SessionError::Length)
+        );
+        let mut too_large = record;
+        too_large[0] = 9;
+        assert_eq!(
+            bob.open_into(&too_large[..len + 1], &mut out),
+            Err(SessionError::TooLarge)
+        );
+        assert_eq!(bob.open_into(&record[..len], &mut out), Ok(8));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_seal_and_open() {
+        let (mut alice, mut bob) = pair();
+        let record = alice.seal(b"hello").unwrap();
+        assert_eq!(bob.open(&record).unwrap(), b"hello");
+        assert_eq!(bob.open(&record[..3]), Err(SessionError::Length));
+    }
+}
@@ -0,0 +1,368 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::meow::Meow;
+
+/// The label identifying the challenge of a signature.
+const CHALLENGE_LABEL: &[u8] = b"ck-meow schnorr-ristretto255 challenge";
+/// The label identifying the nonce of a signature.
+const NONCE_LABEL: &[u8] = b"ck-meow schnorr-ristretto255 nonce";
+
+/// The number of bytes in an encoded signature.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// An error produced when a signature, or a key, is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignatureError;
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature failed to verify.")
+    }
+}
+
+impl core::error::Error for SignatureError {}
+
+/// Compute the challenge of a signature.
+///
+/// The context is used as the protocol string, and the public key, the nonce
+/// commitment, and the message are absorbed with `ad`.
+fn challenge(context: &[u8], public: &[u8; 32], r: &[u8; 32], message: &[u8]) -> Scalar {
+    let mut meow = Meow::new(context);
+    meow.meta_ad(CHALLENGE_LABEL, false);
+    meow.ad(public, false);
+    meow.ad(r, false);
+    meow.ad(message, false);
+    let mut wide = [0u8; 64];
+    meow.prf(&mut wide, false);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+/// A key used to create signatures.
+///
+/// Signatures are Schnorr signatures over the Ristretto255 group, using
+/// Meow for all of the hashing involved.
+///
+/// ```
+/// use ck_meow::SigningKey;
+/// use rand_core::{CryptoRng, RngCore};
+///
+/// fn example(rng: &mut (impl RngCore + CryptoRng)) {
+///     let signing_key = SigningKey::generate(rng);
+///     let signature = signing_key.sign(b"my app", b"a message", rng);
+///
+///     let verifying_key = signing_key.verifying_key();
+///     assert!(verifying_key.verify(b"my app", b"a message", &signature).is_ok());
+///     assert!(verifying_key.verify(b"other app", b"a message", &signature).is_err());
+/// }
+/// ```
+///
+/// Every signature is bound to a context, which is used as the protocol
+/// string passed to `Meow::new`, so that a signature created for one
+/// application never verifies for another.
+///
+/// The nonces are derived from a Meow state keyed with the secret key, along
+/// with some fresh randomness, and the message. This means that a bad source of
+/// randomness can't leak the secret key, and good randomness protects against
+/// fault attacks.
+#[derive(Clone, ZeroizeOnDrop)]
+pub struct SigningKey {
+    secret: Scalar,
+    #[zeroize(skip)]
+    public: VerifyingKey,
+}
+
+impl SigningKey {
+    /// Generate a new key, using a source of randomness.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut wide = Zeroizing::new([0u8; 64]);
+        rng.fill_bytes(&mut wide[..]);
+        Self::from_scalar(Scalar::from_bytes_mod_order_wide(&wide))
+    }
+
+    /// Restore a key encoded with [`Self::to_bytes`].
+    ///
+    /// This fails if the bytes aren't the canonical encoding of a non-zero scalar.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, SignatureError> {
+        let secret = Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes))
+            .filter(|secret| secret != &Scalar::ZERO)
+            .ok_or(SignatureError)?;
+        Ok(Self::from_scalar(secret))
+    }
+
+    /// Encode this key as bytes.
+    ///
+    /// The result gets zeroed when dropped.
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.secret.to_bytes())
+    }
+
+    /// The key used to verify signatures created with this key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.public
+    }
+
+    fn from_scalar(secret: Scalar) -> Self {
+        let point = secret * RISTRETTO_BASEPOINT_POINT;
+        let public = VerifyingKey {
+            point,
+            bytes: point.compress().to_bytes(),
+        };
+        Self { secret, public }
+    }
+
+    /// Sign a message, in a given context.
+    ///
+    /// The randomness is mixed into the nonce, along with the secret key,
+    /// and the message.
+    pub fn sign<R: RngCore + CryptoRng>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> Signature {
+        let mut random = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(&mut random[..]);
+        let mut meow = Meow::new(context);
+        meow.meta_ad(NONCE_LABEL, false);
+        meow.key(self.secret.as_bytes(), false);
+        meow.key(&random[..], false);
+        meow.ad(&self.public.bytes, false);
+        meow.ad(message, false);
+        let mut wide = Zeroizing::new([0u8; 64]);
+        meow.prf(&mut wide[..], false);
+        let mut k = Scalar::from_bytes_mod_order_wide(&wide);
+
+        let r = (k * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+        let c = challenge(context, &self.public.bytes, &r, message);
+        let s = k + c * self.secret;
+        k.zeroize();
+        Signature { r, s }
+    }
+}
+
+/// A key used to verify signatures.
+///
+/// This is created from a [`SigningKey`], with [`SigningKey::verifying_key`].
+#[derive(Clone, Copy, Debug)]
+pub struct VerifyingKey {
+    point: RistrettoPoint,
+    bytes: [u8; 32],
+}
+
+impl PartialEq for VerifyingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl Eq for VerifyingKey {}
+
+impl VerifyingKey {
+    /// Restore a key encoded with [`Self::to_bytes`].
+    ///
+    /// This fails if the bytes don't encode a valid point.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, SignatureError> {
+        let point = CompressedRistretto(*bytes)
+            .decompress()
+            .ok_or(SignatureError)?;
+        Ok(Self {
+            point,
+            bytes: *bytes,
+        })
+    }
+
+    /// Encode this key as bytes.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.bytes
+    }
+
+    /// Verify a signature on a message, in a given context.
+    pub fn verify(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), SignatureError> {
+        let c = challenge(context, &self.bytes, &signature.r, message);
+        let r = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, &-self.point, &signature.s);
+        if r.compress().to_bytes() != signature.r {
+            return Err(SignatureError);
+        }
+        Ok(())
+    }
+
+    /// Verify many signatures at once, all in the same context.
+    ///
+    /// This is faster than verifying each signature on its own, but only tells
+    /// us whether all of the signatures are valid. The randomness is used to
+    /// combine the signatures, so that invalid signatures can't cancel out.
+    #[cfg(feature = "alloc")]
+    pub fn verify_batch<R: RngCore + CryptoRng>(
+        context: &[u8],
+        messages: &[&[u8]],
+        signatures: &[Signature],
+        keys: &[VerifyingKey],
+        rng: &mut R,
+    ) -> Result<(), SignatureError> {
+        if messages.len() != signatures.len() || messages.len() != keys.len() {
+            return Err(SignatureError);
+        }
+        let n = messages.len();
+        let mut scalars = Vec::with_capacity(1 + 2 * n);
+        let mut points = Vec::with_capacity(1 + 2 * n);
+        scalars.push(Scalar::ZERO);
+        points.push(Some(RISTRETTO_BASEPOINT_POINT));
+        for ((message, signature), key) in messages.iter().zip(signatures).zip(keys) {
+            // 128 bits of randomness are enough to make cancellation unlikely.
+            let mut z = [0u8; 32];
+            rng.fill_bytes(&mut z[..16]);
+            let z = Scalar::from_bytes_mod_order(z);
+            let c = challenge(context, &key.bytes, &signature.r, message);
+            scalars[0] += z * signature.s;
+            scalars.push(-z);
+            points.push(CompressedRistretto(signature.r).decompress());
+            scalars.push(-z * c);
+            points.push(Some(key.point));
+        }
+        match RistrettoPoint::optional_multiscalar_mul(scalars, points) {
+            Some(sum) if sum.is_identity() => Ok(()),
+            _ => Err(SignatureError),
+        }
+    }
+}
+
+/// A Schnorr signature over Ristretto255.
+///
+/// This consists of the commitment to the nonce, and the response scalar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    r: [u8; 32],
+    s: Scalar,
+}
+
+impl Signature {
+    /// Restore a signature encoded with [`Self::to_bytes`].
+    ///
+    /// This fails if the response isn't encoded canonically.
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LEN]) -> Result<Self, SignatureError> {
+        let mut r = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&bytes[32..]);
+        let s = Option::from(Scalar::from_canonical_bytes(s)).ok_or(SignatureError)?;
+        Ok(Self { r, s })
+    }
+
+    /// Encode this signature as bytes.
+    ///
+    /// The first 32 bytes are the commitment, and the last 32 bytes the response.
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LEN] {
+        let mut out = [0u8; SIGNATURE_LEN];
+        out[..32].copy_from_slice(&self.r);
+        out[32..].copy_from_slice(self.s.as_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand_core::SeedableRng;
+
+    use super::*;
+    use crate::MeowRng;
+
+    fn setup() -> (SigningKey, MeowRng) {
+        let mut rng = MeowRng::seed_from_u64(0);
+        (SigningKey::generate(&mut rng), rng)
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let (key, mut rng) = setup();
+        let public = key.verifying_key();
+        let signature = key.sign(b"ctx", b"message", &mut rng);
+        assert_eq!(public.verify(b"ctx", b"message", &signature), Ok(()));
+
+        let other = SigningKey::generate(&mut rng).verifying_key();
+        assert!(public.verify(b"ctx", b"other", &signature).is_err());
+        assert!(public.verify(b"other ctx", b"message", &signature).is_err());
+        assert!(other.verify(b"ctx", b"message", &signature).is_err());
+
+        let mut bad = signature.to_bytes();
+        bad[0] ^= 1;
+        let bad = Signature::from_bytes(&bad).unwrap();
+        assert!(public.verify(b"ctx", b"message", &bad).is_err());
+        let mut bad = signature.to_bytes();
+        bad[32] ^= 1;
+        let bad = Signature::from_bytes(&bad).unwrap();
+        assert!(public.verify(b"ctx", b"message", &bad).is_err());
+    }
+
+    #[test]
+    fn test_nonces_are_hedged() {
+        let (key, _) = setup();
+        let sign = |seed| key.sign(b"ctx", b"message", &mut MeowRng::seed_from_u64(seed));
+        assert_eq!(sign(1), sign(1));
+        assert_ne!(sign(1).r, sign(2).r);
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let (key, mut rng) = setup();
+        let restored = SigningKey::from_bytes(&key.to_bytes()).unwrap();
+        assert_eq!(restored.verifying_key(), key.verifying_key());
+        let public = VerifyingKey::from_bytes(&key.verifying_key().to_bytes()).unwrap();
+        assert_eq!(public, key.verifying_key());
+
+        let signature = key.sign(b"ctx", b"message", &mut rng);
+        assert_eq!(Signature::from_bytes(&signature.to_bytes()), Ok(signature));
+
+        assert!(SigningKey::from_bytes(&[0u8; 32]).is_err());
+        assert!(SigningKey::from_bytes(&[0xFF; 32]).is_err());
+        assert!(VerifyingKey::from_bytes(&[0xFF; 32]).is_err());
+        let mut bad = signature.to_bytes();
+        bad[32..].fill(0xFF);
+        assert!(Signature::from_bytes(&bad).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_verify_batch() {
+        let (_, mut rng) = setup();
+        let keys: Vec<SigningKey> = (0..4).map(|_| SigningKey::generate(&mut rng)).collect();
+        let messages: [&[u8]; 4] = [b"a", b"b", b"c", b"d"];
+        let mut signatures: Vec<Signature> = keys
+            .iter()
+            .zip(messages)
+            .map(|(key, message)| key.sign(b"ctx", message, &mut rng))
+            .collect();
+        let public: Vec<VerifyingKey> = keys.iter().map(|key| key.verifying_key()).collect();
+
+        let verify = |signatures: &[Signature], rng: &mut MeowRng| {
+            VerifyingKey::verify_batch(b"ctx", &messages, signatures, &public, rng)
+        };
+        assert_eq!(verify(&signatures, &mut rng), Ok(()));
+        assert!(
+            VerifyingKey::verify_batch(b"ctx", &messages[..3], &signatures, &public, &mut rng)
+                .is_err()
+        );
+        assert!(
+            VerifyingKey::verify_batch(b"other", &messages, &signatures, &public, &mut rng)
+                .is_err()
+        );
+        signatures.swap(0, 1);
+        assert!(verify(&signatures, &mut rng).is_err());
+    }
+}
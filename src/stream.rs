@@ -0,0 +1,453 @@
+use std::io::{self, Read, Write};
+
+use zeroize::Zeroizing;
+
+use crate::meow::{MacError, Meow};
+
+/// The label absorbed at the start of a stream.
+const STREAM_LABEL: &[u8] = b"ck-meow stream";
+/// The number of bytes in the MAC ending each chunk.
+pub const STREAM_MAC_LEN: usize = 16;
+/// The default number of bytes of plaintext in each chunk.
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 16;
+
+/// Prepare a state for a stream, binding it to the chunk size.
+fn start(mut meow: Meow, chunk_size: usize) -> Meow {
+    assert!(chunk_size > 0, "chunk size must not be zero");
+    meow.meta_ad(STREAM_LABEL, false);
+    meow.meta_ad(&(chunk_size as u64).to_le_bytes(), false);
+    meow
+}
+
+/// Absorb the index of a chunk, and whether or not it's the last one.
+fn frame_chunk(meow: &mut Meow, index: u64, last: bool) {
+    meow.meta_ad(&index.to_le_bytes(), false);
+    meow.meta_ad(&[u8::from(last)], true);
+}
+
+/// A writer encrypting data in chunks, each with its own MAC.
+///
+/// This works like the STREAM construction: the data is split into chunks
+/// of a fixed size, with only the last chunk being shorter, possibly empty.
+/// Each chunk is bound to its index, and to whether or not it's the last one,
+/// so that chunks can't be reordered, and the stream can't be truncated.
+/// Starting a stream, and encrypting a chunk, do the following operations:
+///
+/// ```
+/// use ck_meow::Meow;
+///
+/// # let mut meow = Meow::new(b"my protocol");
+/// # let (chunk_size, index, last, mut chunk) = (16u64, 0u64, true, *b"hello");
+/// meow.meta_ad(b"ck-meow stream", false);
+/// meow.meta_ad(&chunk_size.to_le_bytes(), false);
+/// // For each chunk:
+/// meow.meta_ad(&index.to_le_bytes(), false);
+/// meow.meta_ad(&[u8::from(last)], true);
+/// meow.send_enc(&mut chunk, false);
+/// let mut mac = [0u8; 16];
+/// meow.send_mac(&mut mac);
+/// ```
+///
+/// The state should have already absorbed a key, and shouldn't be used
+/// for more than one stream. The stream needs to be ended with [`Self::finish`],
+/// otherwise it will be rejected as truncated. If a chunk fails to be written
+/// to the underlying writer, it can't be written again, so every write after
+/// that fails as well.
+///
+/// ```
+/// use std::io::{Read, Write};
+///
+/// use ck_meow::{DecryptReader, EncryptWriter, Meow};
+///
+/// let mut meow = Meow::new(b"my protocol");
+/// meow.key(b"a shared secret", false);
+///
+/// let mut writer = EncryptWriter::new(meow.clone(), Vec::new());
+/// writer.write_all(b"a lot of data").unwrap();
+/// let encrypted = writer.finish().unwrap();
+///
+/// let mut reader = DecryptReader::new(meow, &encrypted[..]);
+/// let mut data = Vec::new();
+/// reader.read_to_end(&mut data).unwrap();
+/// assert_eq!(data, b"a lot of data");
+/// ```
+pub struct EncryptWriter<W: Write> {
+    meow: Meow,
+    inner: W,
+    chunk_size: usize,
+    /// The plaintext of the current chunk, with room for the MAC.
+    buffer: Zeroizing<Vec<u8>>,
+    index: u64,
+    poisoned: bool,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Create a new writer, using the default chunk size.
+    pub fn new(meow: Meow, inner: W) -> Self {
+        Self::with_chunk_size(meow, DEFAULT_CHUNK_SIZE, inner)
+    }
+
+    /// Create a new writer, with a given number of bytes of plaintext in each chunk.
+    ///
+    /// The reader needs to use the same chunk size.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the chunk size is zero.
+    pub fn with_chunk_size(meow: Meow, chunk_size: usize, inner: W) -> Self {
+        Self {
+            meow: start(meow, chunk_size),
+            inner,
+            chunk_size,
+            buffer: Zeroizing::new(Vec::with_capacity(chunk_size + STREAM_MAC_LEN)),
+            index: 0,
+            poisoned: false,
+        }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other(
+                "stream writer was poisoned by a failed write",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encrypt the buffered chunk, and write it out.
+    fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+        self.check()?;
+        frame_chunk(&mut self.meow, self.index, last);
+        self.meow.send_enc(&mut self.buffer, false);
+        let mut mac = [0u8; STREAM_MAC_LEN];
+        self.meow.send_mac(&mut mac);
+        self.buffer.extend_from_slice(&mac);
+        self.index += 1;
+        let result = self.inner.write_all(&self.buffer);
+        self.buffer.clear();
+        // The chunk has already been sealed, so it can't be written again.
+        self.poisoned = result.is_err();
+        result
+    }
+
+    /// Write out the last chunk, and flush the underlying writer, returning it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    /// Buffer data for the current chunk, returning how much was accepted.
+    ///
+    /// This fills at most one chunk per call, so that an error writing out a
+    /// chunk never happens after part of the data has been accepted.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        // We only know that a full chunk isn't the last one once there's more data.
+        if self.buffer.len() == self.chunk_size && !buf.is_empty() {
+            self.write_chunk(false)?;
+        }
+        let take = buf.len().min(self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..take]);
+        Ok(take)
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// This doesn't write out the current chunk, since only whole chunks can be written.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader decrypting data written by an [`EncryptWriter`].
+///
+/// Each chunk is only returned once its MAC has been verified, so this never
+/// returns unauthenticated data. If a chunk fails to authenticate, or the
+/// stream was truncated, reading returns an error, and every read after that
+/// fails as well. Reaching the end of the data means that the whole stream
+/// was authenticated. Other errors from the underlying reader, like
+/// [`io::ErrorKind::WouldBlock`], are passed on, and reading can be resumed
+/// after them.
+pub struct DecryptReader<R: Read> {
+    meow: Meow,
+    inner: R,
+    chunk_size: usize,
+    /// The current chunk, decrypted in place.
+    buffer: Zeroizing<Vec<u8>>,
+    /// The range of the decrypted chunk which hasn't been returned yet.
+    pos: usize,
+    end: usize,
+    /// The number of bytes of the next chunk read so far.
+    filled: usize,
+    /// A byte read past the end of the previous chunk.
+    lookahead: Option<u8>,
+    index: u64,
+    done: bool,
+    poisoned: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Create a new reader, using the default chunk size.
+    pub fn new(meow: Meow, inner: R) -> Self {
+        Self::with_chunk_size(meow, DEFAULT_CHUNK_SIZE, inner)
+    }
+
+    /// Create a new reader, with a given number of bytes of plaintext in each chunk.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the chunk size is zero.
+    pub fn with_chunk_size(meow: Meow, chunk_size: usize, inner: R) -> Self {
+        Self {
+            meow: start(meow, chunk_size),
+            inner,
+            chunk_size,
+            buffer: Zeroizing::new(vec![0u8; chunk_size + STREAM_MAC_LEN]),
+            pos: 0,
+            end: 0,
+            filled: 0,
+            lookahead: None,
+            index: 0,
+            done: false,
+            poisoned: false,
+        }
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Read as much of the next chunk as possible, stopping only at the end of the data.
+    ///
+    /// The bytes read so far are kept if this fails, so it can be resumed.
+    fn fill(&mut self, full: usize) -> io::Result<()> {
+        if let Some(byte) = self.lookahead.take() {
+            self.buffer[0] = byte;
+            self.filled = 1;
+        }
+        while self.filled < full {
+            match self.inner.read(&mut self.buffer[self.filled..full]) {
+                Ok(0) => break,
+                Ok(n) => self.filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Read, decrypt, and authenticate the next chunk.
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let full = self.chunk_size + STREAM_MAC_LEN;
+        self.fill(full)?;
+        let filled = self.filled;
+        // A chunk is the last one if the data ends without filling it, or right after it.
+        let mut last = filled < full;
+        if !last {
+            let mut byte = [0u8; 1];
+            loop {
+                match self.inner.read(&mut byte) {
+                    Ok(0) => last = true,
+                    Ok(_) => self.lookahead = Some(byte[0]),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+                break;
+            }
+        }
+        let Some(len) = filled.checked_sub(STREAM_MAC_LEN) else {
+            self.poisoned = true;
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, MacError));
+        };
+
+        frame_chunk(&mut self.meow, self.index, last);
+        let (chunk, mac) = self.buffer[..filled].split_at_mut(len);
+        if self.meow.recv_enc_and_mac(chunk, mac).is_err() {
+            self.poisoned = true;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, MacError));
+        }
+        self.index += 1;
+        self.filled = 0;
+        self.pos = 0;
+        self.end = len;
+        self.done = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.poisoned {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, MacError));
+        }
+        while self.pos == self.end {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let take = buf.len().min(self.end - self.pos);
+        buf[..take].copy_from_slice(&self.buffer[self.pos..self.pos + take]);
+        self.pos += take;
+        Ok(take)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::{keyed, seal_by_hand};
+
+    fn encrypt(data: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut writer = EncryptWriter::with_chunk_size(keyed(), chunk_size, Vec::new());
+        // Write in uneven pieces, to cross chunk boundaries.
+        for piece in data.chunks(7) {
+            writer.write_all(piece).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn decrypt(encrypted: &[u8], chunk_size: usize) -> io::Result<Vec<u8>> {
+        let mut reader = DecryptReader::with_chunk_size(keyed(), chunk_size, encrypted);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let encrypted = encrypt(b"hello world", 8);
+
+        let mut meow = keyed();
+        meow.meta_ad(STREAM_LABEL, false);
+        meow.meta_ad(&8u64.to_le_bytes(), false);
+        let mut expected = Vec::new();
+        for (index, chunk) in [&b"hello wo"[..], b"rld"].into_iter().enumerate() {
+            meow.meta_ad(&(index as u64).to_le_bytes(), false);
+            meow.meta_ad(&[u8::from(index == 1)], true);
+            let (chunk, mac) = seal_by_hand(&mut meow, chunk);
+            expected.extend_from_slice(&chunk);
+            expected.extend_from_slice(&mac);
+        }
+        assert_eq!(encrypted, expected);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let data: Vec<u8> = (0..200u8).collect();
+        for chunk_size in [1, 8, 16, 50, 100, 200, 1000] {
+            for len in [0, 1, 15, 16, 17, 100, 200] {
+                let encrypted = encrypt(&data[..len], chunk_size);
+                let chunks = len.div_ceil(chunk_size).max(1);
+                assert_eq!(encrypted.len(), len + chunks * STREAM_MAC_LEN);
+                assert_eq!(decrypt(&encrypted, chunk_size).unwrap(), &data[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncation_is_detected() {
+        let encrypted = encrypt(&[0xAA; 64], 16);
+        for len in 0..encrypted.len() {
+            assert!(decrypt(&encrypted[..len], 16).is_err(), "length {}", len);
+        }
+        let mut extended = encrypted.clone();
+        extended.push(0);
+        assert!(decrypt(&extended, 16).is_err());
+    }
+
+    #[test]
+    fn test_reordering_and_tampering_are_detected() {
+        let encrypted = encrypt(&[0xAA; 64], 16);
+        let chunk = 16 + STREAM_MAC_LEN;
+        let mut reordered = encrypted.clone();
+        reordered[..chunk].copy_from_slice(&encrypted[chunk..2 * chunk]);
+        reordered[chunk..2 * chunk].copy_from_slice(&encrypted[..chunk]);
+        assert_eq!(
+            decrypt(&reordered, 16).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut tampered = encrypted.clone();
+        tampered[chunk + 3] ^= 1;
+        let mut reader = DecryptReader::with_chunk_size(keyed(), 16, &tampered[..]);
+        let mut out = [0u8; 64];
+        // The first chunk is fine, but nothing from the second one gets released.
+        assert_eq!(reader.read(&mut out).unwrap(), 16);
+        assert!(reader.read(&mut out).is_err());
+        assert!(reader.read(&mut out).is_err());
+
+        assert!(decrypt(&encrypted, 32).is_err());
+    }
+
+    /// A reader which only returns a few bytes at a time, failing every other call.
+    struct Flaky<'a> {
+        data: &'a [u8],
+        calls: usize,
+    }
+
+    impl Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.calls += 1;
+            if self.calls % 2 == 0 {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let len = buf.len().min(5);
+            self.data.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_reading_resumes_after_errors() {
+        let data: Vec<u8> = (0..100u8).collect();
+        let encrypted = encrypt(&data, 16);
+        let flaky = Flaky {
+            data: &encrypted,
+            calls: 0,
+        };
+        let mut reader = DecryptReader::with_chunk_size(keyed(), 16, flaky);
+        let mut out = Vec::new();
+        loop {
+            match reader.read_to_end(&mut out) {
+                Ok(_) => break,
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::WouldBlock),
+            }
+        }
+        assert_eq!(out, data);
+    }
+
+    /// A writer which always fails.
+    struct Failing;
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_failed_writes_poison_the_writer() {
+        let mut writer = EncryptWriter::with_chunk_size(keyed(), 8, Failing);
+        // The first chunk only gets written once more data arrives.
+        assert_eq!(writer.write(b"hello world").unwrap(), 8);
+        assert_eq!(
+            writer.write(b"rld").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+        assert!(writer.write(b"rld").is_err());
+        assert!(writer.finish().is_err());
+    }
+}
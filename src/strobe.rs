@@ -0,0 +1,263 @@
+// See: https://strobe.sourceforge.io/specs for the specification for STROBE.
+use crate::{
+    meow::{check_zero, Flags, FLAG_A, FLAG_C, FLAG_I, FLAG_M, FLAG_T},
+    permutation::KeccakF1600,
+    MacError, Meow,
+};
+
+/// The context string used by version 1.0.2 of STROBE.
+const STROBE_CONTEXT: &[u8] = b"STROBEv1.0.2";
+
+/// An instance of STROBE v1.0.2, at a security level of 128 bits.
+///
+/// This is a compatibility mode, which matches the reference implementation
+/// of STROBE exactly, allowing interoperation with other implementations,
+/// like Merlin transcripts. This uses the full 24 round Keccak-f\[1600\]
+/// permutation, along with the original context string.
+///
+/// The operations work just like in [`Meow`], except that we also provide
+/// the `meta` variants of `key` and `prf`, and that MACs can be sent and received
+/// in a streaming fashion, using `more`, as in the original specification.
+///
+/// Unless you need to talk to an existing STROBE implementation, you should
+/// use [`Meow`] instead.
+#[cfg_attr(test, derive(Debug))]
+#[derive(Clone)]
+pub struct Strobe {
+    meow: Meow<128, KeccakF1600>,
+}
+
+impl Strobe {
+    /// Create a new STROBE instance, with a given protocol string.
+    pub fn new(protocol: &[u8]) -> Self {
+        Self {
+            meow: Meow::with_context(&[STROBE_CONTEXT], protocol),
+        }
+    }
+
+    /// Absorb additional data into this state.
+    pub fn ad(&mut self, data: &[u8], more: bool) {
+        self.meow.ad(data, more);
+    }
+
+    /// Absorb additional metadata into this state.
+    pub fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.meow.meta_ad(data, more);
+    }
+
+    /// Include a secret key into the state.
+    pub fn key(&mut self, data: &[u8], more: bool) {
+        self.meow.key(data, more);
+    }
+
+    /// Include a secret key into the state, as metadata.
+    pub fn meta_key(&mut self, data: &[u8], more: bool) {
+        self.meow
+            .begin_op(FLAG_M | FLAG_A | FLAG_C, more, data.len());
+        self.meow.overwrite(data);
+    }
+
+    /// Send some plaintext data to the other party.
+    pub fn send_clr(&mut self, data: &[u8], more: bool) {
+        self.meow.send_clr(data, more);
+    }
+
+    /// Send some plaintext metadata to the other party.
+    pub fn meta_send_clr(&mut self, data: &[u8], more: bool) {
+        self.meow.meta_send_clr(data, more);
+    }
+
+    /// Receive plaintext data.
+    pub fn recv_clr(&mut self, data: &[u8], more: bool) {
+        self.meow.recv_clr(data, more);
+    }
+
+    /// Receive plaintext metadata.
+    pub fn meta_recv_clr(&mut self, data: &[u8], more: bool) {
+        self.meow.meta_recv_clr(data, more);
+    }
+
+    /// Send encrypted data, encrypting the buffer in place.
+    pub fn send_enc(&mut self, data: &mut [u8], more: bool) {
+        self.meow.send_enc(data, more);
+    }
+
+    /// Send encrypted metadata, encrypting the buffer in place.
+    pub fn meta_send_enc(&mut self, data: &mut [u8], more: bool) {
+        self.meow.meta_send_enc(data, more);
+    }
+
+    /// Receive encrypted data, decrypting the buffer in place.
+    pub fn recv_enc(&mut self, data: &mut [u8], more: bool) {
+        self.meow.recv_enc(data, more);
+    }
+
+    /// Receive encrypted metadata, decrypting the buffer in place.
+    pub fn meta_recv_enc(&mut self, data: &mut [u8], more: bool) {
+        self.meow.meta_recv_enc(data, more);
+    }
+
+    /// Send a MAC to the other party.
+    ///
+    /// Unlike [`Meow::send_mac`], this can be split over multiple calls with `more`.
+    pub fn send_mac(&mut self, data: &mut [u8], more: bool) {
+        self.send_mac_with(FLAG_C | FLAG_T, data, more);
+    }
+
+    /// Send a MAC of metadata to the other party.
+    pub fn meta_send_mac(&mut self, data: &mut [u8], more: bool) {
+        self.send_mac_with(FLAG_M | FLAG_C | FLAG_T, data, more);
+    }
+
+    /// Receive and verify a MAC.
+    ///
+    /// Unlike [`Meow::recv_mac`], this can be split over multiple calls with `more`.
+    /// Each call verifies the chunk of the MAC it was given, so every call
+    /// needs to succeed for the entire MAC to be valid.
+    ///
+    /// The buffer will be modified in the process.
+    pub fn recv_mac(&mut self, data: &mut [u8], more: bool) -> Result<(), MacError> {
+        self.recv_mac_with(FLAG_I | FLAG_C | FLAG_T, data, more)
+    }
+
+    /// Receive and verify a MAC of metadata.
+    pub fn meta_recv_mac(&mut self, data: &mut [u8], more: bool) -> Result<(), MacError> {
+        self.recv_mac_with(FLAG_M | FLAG_I | FLAG_C | FLAG_T, data, more)
+    }
+
+    /// Generate random bytes from the state.
+    pub fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.meow.prf(data, more);
+    }
+
+    /// Generate random bytes from the state, as metadata.
+    pub fn meta_prf(&mut self, data: &mut [u8], more: bool) {
+        self.meow
+            .begin_op(FLAG_M | FLAG_I | FLAG_A | FLAG_C, more, data.len());
+        self.meow.squeeze(data);
+    }
+
+    /// Ratchet the state forward, by zeroing out `len` bytes.
+    pub fn ratchet(&mut self, len: usize, more: bool) {
+        self.meow.ratchet_many(len, more);
+    }
+
+    /// Ratchet the state forward, as metadata.
+    pub fn meta_ratchet(&mut self, len: usize, more: bool) {
+        self.meow.begin_op(FLAG_M | FLAG_C, more, len);
+        self.meow.zero_out(len);
+    }
+
+    fn send_mac_with(&mut self, flags: Flags, data: &mut [u8], more: bool) {
+        self.meow.begin_op(flags, more, data.len());
+        self.meow.copy(data);
+    }
+
+    fn recv_mac_with(&mut self, flags: Flags, data: &mut [u8], more: bool) -> Result<(), MacError> {
+        self.meow.begin_op(flags, more, data.len());
+        self.meow.exchange(data);
+        check_zero(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    fn hex_decode(data: &str) -> Vec<u8> {
+        (0..data.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&data[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Run one of the known answer tests from the reference implementation.
+    ///
+    /// These vectors come from the strobe-rs crate, which checked them against
+    /// the python reference implementation of STROBE. They record the entire
+    /// state after each operation, along with the output of some operations.
+    fn run_known_answer_test(json: &str) {
+        let test: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(test["security"], 128);
+        let mut strobe = Strobe::new(test["proto_string"].as_str().unwrap().as_bytes());
+
+        for op in test["operations"].as_array().unwrap() {
+            let name = op["name"].as_str().unwrap();
+            let meta = op["meta"].as_bool().unwrap();
+            let more = op["stream"].as_bool().unwrap();
+            let mut data = hex_decode(op["input_data"].as_str().unwrap());
+            match (name, meta) {
+                ("init", _) => {}
+                ("AD", false) => strobe.ad(&data, more),
+                ("AD", true) => strobe.meta_ad(&data, more),
+                ("KEY", false) => strobe.key(&data, more),
+                ("KEY", true) => strobe.meta_key(&data, more),
+                ("PRF", false) => strobe.prf(&mut data, more),
+                ("PRF", true) => strobe.meta_prf(&mut data, more),
+                ("send_CLR", false) => strobe.send_clr(&data, more),
+                ("send_CLR", true) => strobe.meta_send_clr(&data, more),
+                ("recv_CLR", false) => strobe.recv_clr(&data, more),
+                ("recv_CLR", true) => strobe.meta_recv_clr(&data, more),
+                ("send_ENC", false) => strobe.send_enc(&mut data, more),
+                ("send_ENC", true) => strobe.meta_send_enc(&mut data, more),
+                ("recv_ENC", false) => strobe.recv_enc(&mut data, more),
+                ("recv_ENC", true) => strobe.meta_recv_enc(&mut data, more),
+                ("send_MAC", false) => strobe.send_mac(&mut data, more),
+                ("send_MAC", true) => strobe.meta_send_mac(&mut data, more),
+                // The MACs in the vectors are random, and aren't expected to verify.
+                ("recv_MAC", false) => assert!(strobe.recv_mac(&mut data, more).is_err()),
+                ("recv_MAC", true) => assert!(strobe.meta_recv_mac(&mut data, more).is_err()),
+                // The ratchet input is a string of zeros, of the right length.
+                ("RATCHET", false) => strobe.ratchet(data.len(), more),
+                ("RATCHET", true) => strobe.meta_ratchet(data.len(), more),
+                _ => panic!("unknown operation: {}", name),
+            }
+            let expected_state = hex_decode(op["state_after"].as_str().unwrap());
+            assert_eq!(&strobe.meow.state_bytes()[..], &expected_state, "{}", name);
+            if let Some(output) = op.get("output") {
+                assert_eq!(data, hex_decode(output.as_str().unwrap()), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_simple_vector() {
+        run_known_answer_test(include_str!("../kat/strobe/simple_test_vector.json"));
+    }
+
+    #[test]
+    fn test_meta_vector() {
+        run_known_answer_test(include_str!("../kat/strobe/meta_test_vector.json"));
+    }
+
+    #[test]
+    fn test_streaming_vector() {
+        run_known_answer_test(include_str!("../kat/strobe/streaming_test_vector.json"));
+    }
+
+    #[test]
+    fn test_boundary_vector() {
+        run_known_answer_test(include_str!("../kat/strobe/boundary_test_vector.json"));
+    }
+
+    #[test]
+    fn test_streaming_mac() {
+        let mut mac0 = [0u8; 32];
+        let mut strobe0 = Strobe::new(b"test protocol");
+        strobe0.key(b"key", false);
+        strobe0.send_mac(&mut mac0, false);
+
+        let mut mac1 = [0u8; 32];
+        let mut strobe1 = Strobe::new(b"test protocol");
+        strobe1.key(b"key", false);
+        strobe1.send_mac(&mut mac1[..10], false);
+        strobe1.send_mac(&mut mac1[10..], true);
+        assert_eq!(mac0, mac1);
+
+        let mut strobe2 = Strobe::new(b"test protocol");
+        strobe2.key(b"key", false);
+        assert!(strobe2.recv_mac(&mut mac0[..20], false).is_ok());
+        assert!(strobe2.recv_mac(&mut mac0[20..], true).is_ok());
+    }
+}
@@ -8,6 +8,25 @@
 use crate::meow::Meow;
 use proptest::{collection::vec, prelude::*};
 
+/// A state keyed with a shared secret, for testing the constructions built on Meow.
+pub(crate) fn keyed() -> Meow {
+    let mut meow = Meow::new(b"test");
+    meow.key(b"a shared secret", false);
+    meow
+}
+
+/// Seal a message by hand, with `send_enc` and `send_mac`, returning the ciphertext and the MAC.
+///
+/// Most constructions seal their messages like this, so checking their framing
+/// only needs to spell out what they absorb before each message.
+pub(crate) fn seal_by_hand(meow: &mut Meow, message: &[u8]) -> (Vec<u8>, [u8; 16]) {
+    let mut ciphertext = message.to_vec();
+    meow.send_enc(&mut ciphertext, false);
+    let mut mac = [0u8; 16];
+    meow.send_mac(&mut mac);
+    (ciphertext, mac)
+}
+
 /// Represents a single command in the protocol.
 ///
 /// Each command basically represents an operation we can do with our instance.
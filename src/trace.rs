@@ -0,0 +1,203 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use zeroize::Zeroize;
+
+use crate::{kitten::KittenState, meow::Role, permutation::Permutation};
+
+/// The number of bytes in the fingerprint of a state.
+pub const FINGERPRINT_LEN: usize = 8;
+
+/// A single operation recorded in a trace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The flags of the operation, as absorbed into the state.
+    ///
+    /// These include our role, so a party sending data, and the party
+    /// receiving it will have the same flags.
+    pub flags: u8,
+    /// Whether or not this continues the previous operation.
+    pub more: bool,
+    /// The number of bytes the operation processed.
+    pub len: usize,
+    /// Our role, at the end of the operation.
+    pub role: Role,
+    /// A fingerprint of the state, before the operation.
+    pub fingerprint: [u8; FINGERPRINT_LEN],
+}
+
+impl TraceEntry {
+    /// Check whether two parties agree on this operation.
+    ///
+    /// This ignores their roles, which are expected to differ.
+    fn agrees_with(&self, other: &TraceEntry) -> bool {
+        self.flags == other.flags
+            && self.more == other.more
+            && self.len == other.len
+            && self.fingerprint == other.fingerprint
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "flags={:#08b} more={} len={} role={:?} state=",
+            self.flags, self.more, self.len, self.role
+        )?;
+        for b in self.fingerprint {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// A record of the operations a Meow instance has done.
+///
+/// This is useful to debug two parties whose states have gone out of sync:
+/// comparing their traces with [`Trace::first_divergence`] will point out
+/// which operation they disagreed on.
+///
+/// Note that the fingerprints are derived from the state, and so reveal
+/// a bit of information about it. Traces are meant for debugging, and
+/// shouldn't be recorded in production.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    entries: Vec<TraceEntry>,
+}
+
+/// The first point where two traces diverge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The index of the operation where the traces differ.
+    pub index: usize,
+    /// Our entry at that index, if we have one.
+    pub ours: Option<TraceEntry>,
+    /// Their entry at that index, if they have one.
+    pub theirs: Option<TraceEntry>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "traces diverge at operation {}:", self.index)?;
+        for (name, entry) in [("ours", self.ours), ("theirs", self.theirs)] {
+            match entry {
+                Some(entry) => writeln!(f, "  {}: {}", name, entry)?,
+                None => writeln!(f, "  {}: (none)", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Trace {
+    /// The operations recorded so far, from first to last.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// Find the first operation where two traces disagree.
+    ///
+    /// Two parties executing the same protocol should have the same trace,
+    /// except for their roles. If one trace is shorter than the other,
+    /// they diverge at the end of the shorter trace.
+    pub fn first_divergence(&self, other: &Trace) -> Option<Divergence> {
+        let index = self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .position(|(a, b)| !a.agrees_with(b))
+            .unwrap_or_else(|| self.entries.len().min(other.entries.len()));
+        if index == self.entries.len() && index == other.entries.len() {
+            return None;
+        }
+        Some(Divergence {
+            index,
+            ours: self.entries.get(index).copied(),
+            theirs: other.entries.get(index).copied(),
+        })
+    }
+
+    pub(crate) fn record<P: Permutation>(
+        &mut self,
+        state: &KittenState<P>,
+        flags: u8,
+        more: bool,
+        len: usize,
+        role: Role,
+    ) {
+        let mut copy = state.clone();
+        copy.permute();
+        let mut fingerprint = [0u8; FINGERPRINT_LEN];
+        copy.read(0, &mut fingerprint);
+        copy.zeroize();
+        self.entries.push(TraceEntry {
+            flags,
+            more,
+            len,
+            role,
+            fingerprint,
+        });
+    }
+}
+
+/// Dump the trace, with one line per operation.
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            writeln!(f, "{}: {}", i, entry)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Meow;
+
+    #[test]
+    fn test_synchronized_traces_agree() {
+        let mut meow0 = Meow::new(b"trace test");
+        let mut meow1 = Meow::new(b"trace test");
+        meow0.key(b"key", false);
+        meow1.key(b"key", false);
+        let mut data = *b"hello";
+        meow0.send_enc(&mut data, false);
+        meow1.recv_enc(&mut data, false);
+        let mut mac = [0u8; 16];
+        meow1.send_mac(&mut mac);
+        assert!(meow0.recv_mac(&mut mac).is_ok());
+
+        assert_eq!(meow0.trace().entries().len(), 4);
+        assert_eq!(meow0.trace().first_divergence(meow1.trace()), None);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let mut meow0 = Meow::new(b"trace test");
+        let mut meow1 = Meow::new(b"trace test");
+        meow0.ad(b"same", false);
+        meow1.ad(b"same", false);
+        meow0.ad(b"this", false);
+        meow1.ad(b"that", false);
+        meow0.ad(b"again", false);
+        meow1.ad(b"again", false);
+
+        // The operation with different data has the same shape,
+        // so it's the one after which shows up as diverging.
+        let divergence = meow0.trace().first_divergence(meow1.trace()).unwrap();
+        assert_eq!(divergence.index, 3);
+        assert_ne!(
+            divergence.ours.unwrap().fingerprint,
+            divergence.theirs.unwrap().fingerprint
+        );
+
+        meow0.ad(b"extra", false);
+        let mut trace1 = meow1.take_trace();
+        assert!(meow1.trace().entries().is_empty());
+        trace1.entries.truncate(1);
+        let divergence = meow0.trace().first_divergence(&trace1).unwrap();
+        assert_eq!(divergence.index, 1);
+        assert_eq!(divergence.theirs, None);
+    }
+}
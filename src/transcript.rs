@@ -0,0 +1,259 @@
+#[cfg(feature = "rand_core")]
+use rand_core::{CryptoRng, Error, RngCore};
+#[cfg(feature = "rand_core")]
+use zeroize::Zeroizing;
+
+use crate::meow::Meow;
+
+/// The protocol string used by [`Transcript`].
+const TRANSCRIPT_PROTOCOL: &[u8] = b"ck-meow transcript";
+
+/// A transcript of a public coin protocol, for the Fiat-Shamir transform.
+///
+/// This follows the API of Merlin. The prover and verifier both append the
+/// messages of the protocol to a transcript, and derive challenges from it,
+/// instead of receiving them from each other:
+///
+/// ```
+/// use ck_meow::Transcript;
+///
+/// let mut prover = Transcript::new(b"my proof");
+/// prover.append_message(b"commitment", b"some commitment");
+/// let mut challenge = [0u8; 32];
+/// prover.challenge_bytes(b"challenge", &mut challenge);
+///
+/// let mut verifier = Transcript::new(b"my proof");
+/// verifier.append_message(b"commitment", b"some commitment");
+/// let mut expected = [0u8; 32];
+/// verifier.challenge_bytes(b"challenge", &mut expected);
+/// assert_eq!(challenge, expected);
+/// ```
+///
+/// Each message is framed with its label, and its length, using `meta_ad`,
+/// before absorbing it with `ad`. Challenges are also framed with their label
+/// and length, before being squeezed out with `prf`. This means that two different
+/// sequences of messages never produce the same challenges.
+#[derive(Clone)]
+pub struct Transcript {
+    meow: Meow,
+}
+
+impl Transcript {
+    /// Create a new transcript, with a label identifying the protocol.
+    pub fn new(label: &[u8]) -> Self {
+        let mut out = Self {
+            meow: Meow::new(TRANSCRIPT_PROTOCOL),
+        };
+        out.append_message(b"dom-sep", label);
+        out
+    }
+
+    /// Append a message to the transcript, along with a label.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        frame_labelled(&mut self.meow, label, message.len());
+        self.meow.ad(message, false);
+    }
+
+    /// Append an integer to the transcript, along with a label.
+    ///
+    /// The integer is encoded in little endian, as 8 bytes.
+    pub fn append_u64(&mut self, label: &[u8], x: u64) {
+        self.append_message(label, &x.to_le_bytes());
+    }
+
+    /// Fill a buffer with a challenge, depending on everything appended so far.
+    pub fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+        frame_labelled(&mut self.meow, label, out.len());
+        self.meow.prf(out, false);
+    }
+
+    /// Start building a random number generator bound to this transcript.
+    ///
+    /// This is useful for generating the prover's nonces. The generator depends
+    /// on the transcript, on the prover's secret witnesses, and on external
+    /// randomness, so that even a weak source of randomness can't leak the witnesses.
+    ///
+    /// ```
+    /// use ck_meow::Transcript;
+    /// use rand_core::{CryptoRng, RngCore};
+    ///
+    /// fn nonce(transcript: &Transcript, secret: &[u8], rng: &mut (impl RngCore + CryptoRng)) -> [u8; 32] {
+    ///     let mut rng = transcript
+    ///         .build_rng()
+    ///         .rekey_with_witness_bytes(b"secret key", secret)
+    ///         .finalize(rng);
+    ///     let mut nonce = [0u8; 32];
+    ///     rng.fill_bytes(&mut nonce);
+    ///     nonce
+    /// }
+    /// ```
+    ///
+    /// Building the generator doesn't modify the transcript.
+    #[cfg(feature = "rand_core")]
+    pub fn build_rng(&self) -> TranscriptRngBuilder {
+        TranscriptRngBuilder {
+            meow: self.meow.clone(),
+        }
+    }
+}
+
+/// Absorb a label, and the length of the data following it, with `meta_ad`.
+fn frame_labelled(meow: &mut Meow, label: &[u8], len: usize) {
+    meow.meta_ad(label, false);
+    meow.meta_ad(&(len as u64).to_le_bytes(), true);
+}
+
+/// Builds a [`TranscriptRng`], by mixing in the prover's secrets.
+///
+/// This is created with [`Transcript::build_rng`].
+#[cfg(feature = "rand_core")]
+pub struct TranscriptRngBuilder {
+    meow: Meow,
+}
+
+#[cfg(feature = "rand_core")]
+impl TranscriptRngBuilder {
+    /// Mix a secret witness into the generator, along with a label.
+    ///
+    /// The witness is absorbed with `key`.
+    pub fn rekey_with_witness_bytes(mut self, label: &[u8], witness: &[u8]) -> Self {
+        frame_labelled(&mut self.meow, label, witness.len());
+        self.meow.key(witness, false);
+        self
+    }
+
+    /// Finish building the generator, mixing in randomness from another generator.
+    pub fn finalize<R: RngCore + CryptoRng>(mut self, rng: &mut R) -> TranscriptRng {
+        let mut random = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(&mut random[..]);
+        frame_labelled(&mut self.meow, b"rng", random.len());
+        self.meow.key(&random[..], false);
+        TranscriptRng { meow: self.meow }
+    }
+}
+
+/// A random number generator bound to a transcript, and the prover's secrets.
+///
+/// This is created with [`TranscriptRngBuilder::finalize`].
+#[cfg(feature = "rand_core")]
+pub struct TranscriptRng {
+    meow: Meow,
+}
+
+#[cfg(feature = "rand_core")]
+impl RngCore for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.meow.meta_ad(&(dest.len() as u64).to_le_bytes(), false);
+        self.meow.prf(dest, false);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl CryptoRng for TranscriptRng {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_documented_framing() {
+        let mut transcript = Transcript::new(b"test");
+        transcript.append_message(b"msg", b"hello");
+        transcript.append_u64(b"x", 7);
+        let mut challenge = [0u8; 16];
+        transcript.challenge_bytes(b"c", &mut challenge);
+
+        let mut meow = Meow::new(TRANSCRIPT_PROTOCOL);
+        for (label, message) in [
+            (&b"dom-sep"[..], &b"test"[..]),
+            (b"msg", b"hello"),
+            (b"x", &7u64.to_le_bytes()),
+        ] {
+            meow.meta_ad(label, false);
+            meow.meta_ad(&(message.len() as u64).to_le_bytes(), true);
+            meow.ad(message, false);
+        }
+        meow.meta_ad(b"c", false);
+        meow.meta_ad(&16u64.to_le_bytes(), true);
+        let mut expected = [0u8; 16];
+        meow.prf(&mut expected, false);
+
+        assert_eq!(challenge, expected);
+    }
+
+    #[test]
+    fn test_framing_prevents_collisions() {
+        let challenge = |messages: &[(&[u8], &[u8])]| {
+            let mut transcript = Transcript::new(b"test");
+            for (label, message) in messages {
+                transcript.append_message(label, message);
+            }
+            let mut out = [0u8; 32];
+            transcript.challenge_bytes(b"c", &mut out);
+            out
+        };
+        let cases: [&[(&[u8], &[u8])]; 5] = [
+            &[(b"a", b"bc")],
+            &[(b"ab", b"c")],
+            &[(b"a", b"b"), (b"", b"c")],
+            &[(b"a", b"")],
+            &[],
+        ];
+        for (i, a) in cases.iter().enumerate() {
+            for b in &cases[i + 1..] {
+                assert_ne!(challenge(a), challenge(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_challenges_depend_on_label_and_length() {
+        let mut transcript = Transcript::new(b"test");
+        let mut t0 = transcript.clone();
+        let mut t1 = transcript.clone();
+        let mut out = [0u8; 32];
+        let mut out0 = [0u8; 32];
+        let mut out1 = [0u8; 16];
+        transcript.challenge_bytes(b"c", &mut out);
+        t0.challenge_bytes(b"d", &mut out0);
+        t1.challenge_bytes(b"c", &mut out1);
+        assert_ne!(out, out0);
+        assert_ne!(out[..16], out1);
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn test_rng_depends_on_everything() {
+        use rand_core::SeedableRng;
+
+        use crate::MeowRng;
+
+        let nonce = |label: &[u8], witness: &[u8], seed: u64| {
+            let mut transcript = Transcript::new(b"test");
+            transcript.append_message(b"msg", label);
+            let mut rng = transcript
+                .build_rng()
+                .rekey_with_witness_bytes(b"witness", witness)
+                .finalize(&mut MeowRng::seed_from_u64(seed));
+            rng.next_u64()
+        };
+        let base = nonce(b"a", b"secret", 0);
+        assert_eq!(base, nonce(b"a", b"secret", 0));
+        assert_ne!(base, nonce(b"b", b"secret", 0));
+        assert_ne!(base, nonce(b"a", b"secreT", 0));
+        assert_ne!(base, nonce(b"a", b"secret", 1));
+    }
+}